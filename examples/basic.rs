@@ -8,9 +8,18 @@ fn main() {
         vsync: true,
         show_cursor: true,
         headless: false,
+        null_backend: false,
         resizable: true,
+        canvas: None,
+        canvas_sizing: uni_app::CanvasSizing::Fixed,
+        prevent_default: uni_app::PreventDefault::All,
+        listen_on_document: false,
         fullscreen: false,
+        window_mode: uni_app::WindowMode::Windowed,
         intercept_close_request: false,
+        touch_to_mouse: false,
+        record: None,
+        replay: None,
     });
     // start game loop
     app.run(move |app: &mut uni_app::App| {
@@ -18,11 +27,11 @@ fn main() {
             // print on stdout (native) or js console (web)
             uni_app::App::print(format!("{:?}\n", evt));
             // exit on key ou mouse press
-            match evt {
-                &uni_app::AppEvent::KeyUp(_) => {
+            match *evt {
+                uni_app::AppEvent::KeyUp(_) => {
                     uni_app::App::exit();
                 }
-                &uni_app::AppEvent::MouseUp(_) => {
+                uni_app::AppEvent::MouseUp(_) => {
                     uni_app::App::exit();
                 }
                 _ => (),
@@ -0,0 +1,268 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::ErrorKind;
+use std::str::FromStr;
+
+use {AppEvent, File, FileSystem, InputState, IoError, KeyCode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// a physical input triggering an action
+pub enum Binding {
+    /// a keyboard key
+    Key(KeyCode),
+    /// a mouse button (0=left, 1=middle, 2=right, ...)
+    MouseButton(usize),
+    /// a pair of keys acting as an axis : the first one for -1.0, the second one for 1.0
+    KeyAxis(KeyCode, KeyCode),
+}
+
+impl fmt::Display for Binding {
+    /// format used in the bindings file : `KeyW`, `Mouse0` or `KeyA/KeyD`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Binding::Key(code) => write!(f, "{}", code),
+            Binding::MouseButton(button) => write!(f, "Mouse{}", button),
+            Binding::KeyAxis(neg, pos) => write!(f, "{}/{}", neg, pos),
+        }
+    }
+}
+
+impl FromStr for Binding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Binding, String> {
+        let s = s.trim();
+        let parse_key = |k: &str| {
+            let k = k.trim();
+            if k.is_empty() {
+                return Err("missing key".to_owned());
+            }
+            k.parse::<KeyCode>()
+                .map_err(|_| format!("unknown key {:?}", k))
+        };
+        if let Some(sep) = s.find('/') {
+            return Ok(Binding::KeyAxis(
+                parse_key(&s[..sep])?,
+                parse_key(&s[sep + 1..])?,
+            ));
+        }
+        if let Some(Ok(button)) = s.strip_prefix("Mouse").map(str::parse) {
+            return Ok(Binding::MouseButton(button));
+        }
+        parse_key(s).map(Binding::Key)
+    }
+}
+
+impl Binding {
+    fn value(&self, input: &InputState) -> f32 {
+        match *self {
+            Binding::Key(code) => bool_value(input.key_down(code)),
+            Binding::MouseButton(button) => bool_value(input.mouse_button_down(button)),
+            Binding::KeyAxis(neg, pos) => {
+                bool_value(input.key_down(pos)) - bool_value(input.key_down(neg))
+            }
+        }
+    }
+
+    fn pressed(&self, input: &InputState) -> bool {
+        match *self {
+            Binding::Key(code) => input.key_pressed(code),
+            Binding::MouseButton(button) => input.mouse_button_pressed(button),
+            Binding::KeyAxis(neg, pos) => input.key_pressed(neg) || input.key_pressed(pos),
+        }
+    }
+
+    fn released(&self, input: &InputState) -> bool {
+        match *self {
+            Binding::Key(code) => input.key_released(code),
+            Binding::MouseButton(button) => input.mouse_button_released(button),
+            Binding::KeyAxis(neg, pos) => input.key_released(neg) || input.key_released(pos),
+        }
+    }
+}
+
+fn bool_value(b: bool) -> f32 {
+    if b {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// named actions ("jump", "move_x") bound to keys and mouse buttons.
+/// Call [`ActionMap::update`] every frame with the frame events, then query the actions state.
+/// Bindings can be saved in a text file, one action per line :
+/// ```text
+/// jump = Space, Mouse0
+/// move_x = KeyA/KeyD, ArrowLeft/ArrowRight
+/// ```
+pub struct ActionMap {
+    bindings: BTreeMap<String, Vec<Binding>>,
+    input: InputState,
+}
+
+impl ActionMap {
+    pub fn new() -> ActionMap {
+        ActionMap::default()
+    }
+
+    /// add a binding to an action
+    pub fn bind(&mut self, action: &str, binding: Binding) {
+        let bindings = self.bindings.entry(action.to_owned()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// remove all the bindings of an action
+    pub fn unbind(&mut self, action: &str) {
+        self.bindings.remove(action);
+    }
+
+    /// the bindings of an action
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.bindings.get(action).map_or(&[], |b| &b[..])
+    }
+
+    /// the names of all the bound actions
+    pub fn actions(&self) -> Vec<&str> {
+        self.bindings.keys().map(|k| &k[..]).collect()
+    }
+
+    /// update the actions state with the events of the current frame (`App.events`)
+    pub fn update(&mut self, events: &[AppEvent]) {
+        self.input.update(events);
+    }
+
+    /// whether one of the action's bindings is down
+    pub fn down(&self, action: &str) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|b| b.value(&self.input) != 0.0)
+    }
+
+    /// whether one of the action's bindings has been pressed during this frame
+    pub fn pressed(&self, action: &str) -> bool {
+        self.bindings(action).iter().any(|b| b.pressed(&self.input))
+    }
+
+    /// whether one of the action's bindings has been released during this frame
+    pub fn released(&self, action: &str) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|b| b.released(&self.input))
+    }
+
+    /// value of the action between -1.0 and 1.0. Key and mouse bindings count as 1.0 when down
+    pub fn axis(&self, action: &str) -> f32 {
+        let value: f32 = self
+            .bindings(action)
+            .iter()
+            .map(|b| b.value(&self.input))
+            .sum();
+        value.clamp(-1.0, 1.0)
+    }
+
+    /// parse a bindings file content
+    pub fn parse(text: &str) -> Result<ActionMap, IoError> {
+        let mut map = ActionMap::new();
+        for (num, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |msg: String| {
+                IoError::new(
+                    ErrorKind::InvalidData,
+                    format!("line {} : {}", num + 1, msg),
+                )
+            };
+            let sep = line
+                .find('=')
+                .ok_or_else(|| error("missing '='".to_owned()))?;
+            let action = line[..sep].trim();
+            for binding in line[sep + 1..].split(',').filter(|b| !b.trim().is_empty()) {
+                map.bind(action, binding.parse().map_err(error)?);
+            }
+        }
+        Ok(map)
+    }
+
+    /// load the bindings from a file opened with [`FileSystem::open`].
+    /// On web target, wait for [`File::is_ready`] before calling this.
+    pub fn load(file: &mut File) -> Result<ActionMap, IoError> {
+        ActionMap::parse(&file.read_text()?)
+    }
+
+    /// save the bindings in a file, see [`FileSystem::write`]
+    pub fn save(&self, path: &str) -> Result<(), IoError> {
+        FileSystem::write(path, self.to_string().as_bytes())
+    }
+}
+
+impl fmt::Display for ActionMap {
+    /// the bindings file content
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (action, bindings) in &self.bindings {
+            let bindings: Vec<String> = bindings.iter().map(|b| b.to_string()).collect();
+            writeln!(f, "{} = {}", action, bindings.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binding_round_trip() {
+        for binding in &[
+            Binding::Key(KeyCode::KeyW),
+            Binding::Key(KeyCode::Unidentified(42)),
+            Binding::MouseButton(0),
+            Binding::MouseButton(4),
+            Binding::KeyAxis(KeyCode::KeyA, KeyCode::KeyD),
+            Binding::KeyAxis(KeyCode::Unidentified(7), KeyCode::Unidentified(8)),
+        ] {
+            assert_eq!(binding.to_string().parse(), Ok(*binding));
+        }
+    }
+
+    #[test]
+    fn binding_format() {
+        assert_eq!(Binding::Key(KeyCode::Space).to_string(), "Space");
+        assert_eq!(
+            Binding::Key(KeyCode::Unidentified(42)).to_string(),
+            "Unidentified(42)"
+        );
+        assert_eq!(Binding::MouseButton(2).to_string(), "Mouse2");
+        assert_eq!(
+            Binding::KeyAxis(KeyCode::ArrowLeft, KeyCode::ArrowRight).to_string(),
+            "ArrowLeft/ArrowRight"
+        );
+        assert_eq!(
+            " KeyA / KeyD ".parse(),
+            Ok(Binding::KeyAxis(KeyCode::KeyA, KeyCode::KeyD))
+        );
+        assert!("Mouse".parse::<Binding>().is_err());
+        assert!("KeyA/".parse::<Binding>().is_err());
+    }
+
+    #[test]
+    fn action_map_round_trip() {
+        let mut map = ActionMap::new();
+        map.bind("jump", Binding::Key(KeyCode::Space));
+        map.bind("jump", Binding::MouseButton(0));
+        map.bind("move_x", Binding::KeyAxis(KeyCode::KeyA, KeyCode::KeyD));
+        map.bind("special", Binding::Key(KeyCode::Unidentified(42)));
+        let text = map.to_string();
+        let loaded = ActionMap::parse(&text).unwrap();
+        assert_eq!(loaded.actions(), map.actions());
+        for action in map.actions() {
+            assert_eq!(loaded.bindings(action), map.bindings(action));
+        }
+        assert_eq!(loaded.to_string(), text);
+    }
+}
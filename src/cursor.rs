@@ -0,0 +1,62 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+/// standard mouse cursor shapes, see `App.set_cursor`
+pub enum CursorIcon {
+    /// the platform default cursor, usually an arrow
+    #[default]
+    Default,
+    /// text selection (I-beam)
+    Text,
+    /// pointing hand, for links and buttons
+    Hand,
+    Crosshair,
+    /// the program is busy
+    Wait,
+    /// the program is busy but still interactive
+    Progress,
+    Help,
+    Move,
+    NotAllowed,
+    Grab,
+    Grabbing,
+    /// resize the east (right) border
+    EResize,
+    /// resize the north (top) border
+    NResize,
+    /// resize the north-east (top right) corner
+    NeResize,
+    /// resize the north-west (top left) corner
+    NwResize,
+    /// resize the south (bottom) border
+    SResize,
+    /// resize the south-east (bottom right) corner
+    SeResize,
+    /// resize the south-west (bottom left) corner
+    SwResize,
+    /// resize the west (left) border
+    WResize,
+    /// horizontal resize
+    EwResize,
+    /// vertical resize
+    NsResize,
+    /// diagonal resize, top right to bottom left
+    NeswResize,
+    /// diagonal resize, top left to bottom right
+    NwseResize,
+}
+
+/// check that a custom cursor image contains `width` x `height` rgba pixels
+pub(crate) fn check_cursor_image(rgba: &[u8], width: u32, height: u32) -> Result<(), String> {
+    let size = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(4));
+    if size == Some(rgba.len()) {
+        Ok(())
+    } else {
+        Err(format!(
+            "the image is {} bytes long, expected {}x{} rgba pixels",
+            rgba.len(),
+            width,
+            height
+        ))
+    }
+}
@@ -0,0 +1,169 @@
+use AppEvent;
+
+/// number of buttons in the standard gamepad layout
+pub const GAMEPAD_BUTTON_COUNT: usize = 17;
+/// number of axes in the standard gamepad layout
+pub const GAMEPAD_AXIS_COUNT: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// gamepad buttons, in the order of the W3C [standard gamepad](https://w3c.github.io/gamepad/#remapping) layout.
+/// Face buttons are named after their position : `South` is A on Xbox controllers, cross on PlayStation ones.
+pub enum GamepadButton {
+    South,
+    East,
+    West,
+    North,
+    LeftShoulder,
+    RightShoulder,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+    Home,
+}
+
+const BUTTONS: [GamepadButton; GAMEPAD_BUTTON_COUNT] = [
+    GamepadButton::South,
+    GamepadButton::East,
+    GamepadButton::West,
+    GamepadButton::North,
+    GamepadButton::LeftShoulder,
+    GamepadButton::RightShoulder,
+    GamepadButton::LeftTrigger,
+    GamepadButton::RightTrigger,
+    GamepadButton::Select,
+    GamepadButton::Start,
+    GamepadButton::LeftStick,
+    GamepadButton::RightStick,
+    GamepadButton::DPadUp,
+    GamepadButton::DPadDown,
+    GamepadButton::DPadLeft,
+    GamepadButton::DPadRight,
+    GamepadButton::Home,
+];
+
+impl GamepadButton {
+    /// the button at this index in the standard layout
+    pub fn from_index(index: usize) -> Option<GamepadButton> {
+        BUTTONS.get(index).cloned()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// gamepad analog axes. Sticks range from -1.0 (left/up) to 1.0 (right/down),
+/// triggers from 0.0 (released) to 1.0 (fully pressed)
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+const AXES: [GamepadAxis; GAMEPAD_AXIS_COUNT] = [
+    GamepadAxis::LeftStickX,
+    GamepadAxis::LeftStickY,
+    GamepadAxis::RightStickX,
+    GamepadAxis::RightStickY,
+    GamepadAxis::LeftTrigger,
+    GamepadAxis::RightTrigger,
+];
+
+impl GamepadAxis {
+    /// the axis at this index in the standard layout
+    pub fn from_index(index: usize) -> Option<GamepadAxis> {
+        AXES.get(index).cloned()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// data associated with a gamepad button press/release event
+pub struct GamepadButtonEvent {
+    /// the gamepad id, as in [`AppEvent::GamepadConnected`]
+    pub gamepad: usize,
+    pub button: GamepadButton,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// data associated with a gamepad axis motion event
+pub struct GamepadAxisEvent {
+    /// the gamepad id, as in [`AppEvent::GamepadConnected`]
+    pub gamepad: usize,
+    pub axis: GamepadAxis,
+    /// the new axis value
+    pub value: f32,
+}
+
+#[derive(Debug, Clone)]
+/// current state of a connected gamepad. Get it with `App.gamepad` or `App.gamepads`
+pub struct GamepadState {
+    /// the gamepad id, as in [`AppEvent::GamepadConnected`]
+    pub id: usize,
+    /// the device name reported by the system
+    pub name: String,
+    buttons: [bool; GAMEPAD_BUTTON_COUNT],
+    axes: [f32; GAMEPAD_AXIS_COUNT],
+}
+
+impl GamepadState {
+    pub(crate) fn new(id: usize, name: String) -> GamepadState {
+        GamepadState {
+            id,
+            name,
+            buttons: [false; GAMEPAD_BUTTON_COUNT],
+            axes: [0.0; GAMEPAD_AXIS_COUNT],
+        }
+    }
+
+    /// whether a button is currently pressed
+    pub fn button(&self, button: GamepadButton) -> bool {
+        self.buttons[button as usize]
+    }
+
+    /// current value of an axis
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes[axis as usize]
+    }
+
+    /// update a button, pushing a GamepadButtonDown/Up event if its state changed
+    pub(crate) fn set_button(
+        &mut self,
+        button: GamepadButton,
+        pressed: bool,
+        events: &mut Vec<AppEvent>,
+    ) {
+        if self.buttons[button as usize] == pressed {
+            return;
+        }
+        self.buttons[button as usize] = pressed;
+        let event = GamepadButtonEvent {
+            gamepad: self.id,
+            button,
+        };
+        events.push(if pressed {
+            AppEvent::GamepadButtonDown(event)
+        } else {
+            AppEvent::GamepadButtonUp(event)
+        });
+    }
+
+    /// update an axis, pushing a GamepadAxis event if its value changed
+    pub(crate) fn set_axis(&mut self, axis: GamepadAxis, value: f32, events: &mut Vec<AppEvent>) {
+        if self.axes[axis as usize] == value {
+            return;
+        }
+        self.axes[axis as usize] = value;
+        events.push(AppEvent::GamepadAxis(GamepadAxisEvent {
+            gamepad: self.id,
+            axis,
+            value,
+        }));
+    }
+}
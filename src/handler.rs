@@ -0,0 +1,72 @@
+use std::cell::Cell;
+
+use App;
+use AppEvent;
+
+thread_local! {
+    /// set by `App::exit`, checked at the end of every frame
+    static EXIT_REQUESTED: Cell<bool> = const { Cell::new(false) };
+}
+
+pub(crate) fn set_exit_requested(requested: bool) {
+    EXIT_REQUESTED.with(|e| e.set(requested));
+}
+
+pub(crate) fn exit_requested() -> bool {
+    EXIT_REQUESTED.with(Cell::get)
+}
+
+/// application callbacks, an alternative to the closure of `App.run`. See `App.run_handler`.
+///
+/// Every frame, `event` is called for each event of `App.events`, then `update` and `render`.
+/// All the methods do nothing by default.
+pub trait AppHandler {
+    /// called once before the first frame
+    fn init(&mut self, _app: &mut App) {}
+
+    /// called for each event of the frame, in order.
+    /// `Resized`, `FocusGained` and `FocusLost` events also call `resized` and `focus_changed` first
+    fn event(&mut self, _app: &mut App, _event: &AppEvent) {}
+
+    /// the window (native) or canvas backing store (web) was resized, in pixels
+    fn resized(&mut self, _app: &mut App, _size: (u32, u32)) {}
+
+    /// the window gained (true) or lost (false) the keyboard focus
+    fn focus_changed(&mut self, _app: &mut App, _focused: bool) {}
+
+    /// update the game state. `dt` is the time since the previous frame in seconds
+    fn update(&mut self, _app: &mut App, _dt: f64) {}
+
+    /// draw the frame
+    fn render(&mut self, _app: &mut App) {}
+
+    /// called once when the game loop stops : window closed or `App::exit`.
+    /// On web target, also called when the page is hidden before being closed or replaced
+    fn exit(&mut self, _app: &mut App) {}
+}
+
+/// the handler used by `App.run`, calling the closure in `update`
+pub(crate) struct FnHandler<F>(pub F);
+
+impl<F: FnMut(&mut App)> AppHandler for FnHandler<F> {
+    fn update(&mut self, app: &mut App, _dt: f64) {
+        (self.0)(app)
+    }
+}
+
+/// call the handler for a frame, once the events are collected in `App.events`
+pub(crate) fn handle_frame<H: AppHandler>(app: &mut App, handler: &mut H, dt: f64) {
+    // the handler can inject events or use App.events
+    let events = app.events.borrow().clone();
+    for event in &events {
+        match *event {
+            AppEvent::Resized(size) => handler.resized(app, size),
+            AppEvent::FocusGained => handler.focus_changed(app, true),
+            AppEvent::FocusLost => handler.focus_changed(app, false),
+            _ => (),
+        }
+        handler.event(app, event);
+    }
+    handler.update(app, dt);
+    handler.render(app);
+}
@@ -0,0 +1,103 @@
+use std::collections::HashSet;
+
+use AppEvent;
+use KeyCode;
+
+#[derive(Debug, Clone, Default)]
+/// keyboard and mouse state, updated by the App from the events queue before each frame.
+/// Get it with `App.input`
+pub struct InputState {
+    keys_down: HashSet<KeyCode>,
+    keys_pressed: HashSet<KeyCode>,
+    keys_released: HashSet<KeyCode>,
+    buttons_down: HashSet<usize>,
+    buttons_pressed: HashSet<usize>,
+    buttons_released: HashSet<usize>,
+    mouse_pos: (f64, f64),
+    text: String,
+}
+
+impl InputState {
+    /// whether a key is currently down
+    pub fn key_down(&self, code: KeyCode) -> bool {
+        self.keys_down.contains(&code)
+    }
+
+    /// whether a key has been pressed during this frame (auto-repeat events are ignored)
+    pub fn key_pressed(&self, code: KeyCode) -> bool {
+        self.keys_pressed.contains(&code)
+    }
+
+    /// whether a key has been released during this frame
+    pub fn key_released(&self, code: KeyCode) -> bool {
+        self.keys_released.contains(&code)
+    }
+
+    /// whether a mouse button is currently down (0=left, 1=middle, 2=right, ...)
+    pub fn mouse_button_down(&self, button: usize) -> bool {
+        self.buttons_down.contains(&button)
+    }
+
+    /// whether a mouse button has been pressed during this frame
+    pub fn mouse_button_pressed(&self, button: usize) -> bool {
+        self.buttons_pressed.contains(&button)
+    }
+
+    /// whether a mouse button has been released during this frame
+    pub fn mouse_button_released(&self, button: usize) -> bool {
+        self.buttons_released.contains(&button)
+    }
+
+    /// last known mouse cursor position, like in `AppEvent::MousePos`
+    pub fn mouse_pos(&self) -> (f64, f64) {
+        self.mouse_pos
+    }
+
+    /// characters typed during this frame
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// start a new frame with the events received since the previous one
+    pub(crate) fn update(&mut self, events: &[AppEvent]) {
+        self.keys_pressed.clear();
+        self.keys_released.clear();
+        self.buttons_pressed.clear();
+        self.buttons_released.clear();
+        self.text.clear();
+        for evt in events {
+            match *evt {
+                AppEvent::KeyDown(ref e) => {
+                    let newly_down = self.keys_down.insert(e.key_code);
+                    if newly_down && !e.repeat {
+                        self.keys_pressed.insert(e.key_code);
+                    }
+                }
+                AppEvent::KeyUp(ref e) => {
+                    self.keys_down.remove(&e.key_code);
+                    self.keys_released.insert(e.key_code);
+                }
+                AppEvent::MouseDown(ref e) => {
+                    self.buttons_down.insert(e.button);
+                    self.buttons_pressed.insert(e.button);
+                }
+                AppEvent::MouseUp(ref e) => {
+                    self.buttons_down.remove(&e.button);
+                    self.buttons_released.insert(e.button);
+                }
+                AppEvent::MousePos(pos) => self.mouse_pos = pos,
+                AppEvent::CharEvent(c) => self.text.push(c),
+                // key release events are lost while the window is not focused
+                AppEvent::FocusLost => self.reset(),
+                _ => (),
+            }
+        }
+    }
+
+    /// forget all keys and buttons, when the window loses the focus
+    fn reset(&mut self) {
+        let mouse_pos = self.mouse_pos;
+        *self = InputState::default();
+        self.mouse_pos = mouse_pos;
+    }
+}
@@ -0,0 +1,268 @@
+use std::fmt;
+use std::str::FromStr;
+
+macro_rules! key_codes {
+    ($($name:ident,)*) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        /// physical key identifier, named after the W3C
+        /// [`KeyboardEvent.code`](https://www.w3.org/TR/uievents-code/) values.
+        /// Keys are identified by their position on a US keyboard : the top left letter is `KeyQ` even on an azerty keyboard.
+        pub enum KeyCode {
+            $($name,)*
+            /// a key without a W3C name. Contains the raw platform scancode (0 on web target)
+            Unidentified(u32),
+        }
+
+        pub(crate) const KEY_CODES: &[KeyCode] = &[$(KeyCode::$name,)*];
+
+        impl KeyCode {
+            /// the W3C name of this key code. Unlike `to_string`, doesn't contain the scancode of
+            /// `Unidentified` keys
+            pub fn name(&self) -> &'static str {
+                match *self {
+                    $(KeyCode::$name => stringify!($name),)*
+                    KeyCode::Unidentified(_) => "Unidentified",
+                }
+            }
+        }
+    };
+}
+
+key_codes! {
+    Backquote,
+    Backslash,
+    BracketLeft,
+    BracketRight,
+    Comma,
+    Digit0,
+    Digit1,
+    Digit2,
+    Digit3,
+    Digit4,
+    Digit5,
+    Digit6,
+    Digit7,
+    Digit8,
+    Digit9,
+    Equal,
+    IntlBackslash,
+    IntlRo,
+    IntlYen,
+    KeyA,
+    KeyB,
+    KeyC,
+    KeyD,
+    KeyE,
+    KeyF,
+    KeyG,
+    KeyH,
+    KeyI,
+    KeyJ,
+    KeyK,
+    KeyL,
+    KeyM,
+    KeyN,
+    KeyO,
+    KeyP,
+    KeyQ,
+    KeyR,
+    KeyS,
+    KeyT,
+    KeyU,
+    KeyV,
+    KeyW,
+    KeyX,
+    KeyY,
+    KeyZ,
+    Minus,
+    Period,
+    Quote,
+    Semicolon,
+    Slash,
+    AltLeft,
+    AltRight,
+    Backspace,
+    CapsLock,
+    ContextMenu,
+    ControlLeft,
+    ControlRight,
+    Enter,
+    MetaLeft,
+    MetaRight,
+    ShiftLeft,
+    ShiftRight,
+    Space,
+    Tab,
+    Convert,
+    KanaMode,
+    Lang1,
+    Lang2,
+    NonConvert,
+    Delete,
+    End,
+    Help,
+    Home,
+    Insert,
+    PageDown,
+    PageUp,
+    ArrowDown,
+    ArrowLeft,
+    ArrowRight,
+    ArrowUp,
+    NumLock,
+    Numpad0,
+    Numpad1,
+    Numpad2,
+    Numpad3,
+    Numpad4,
+    Numpad5,
+    Numpad6,
+    Numpad7,
+    Numpad8,
+    Numpad9,
+    NumpadAdd,
+    NumpadComma,
+    NumpadDecimal,
+    NumpadDivide,
+    NumpadEnter,
+    NumpadEqual,
+    NumpadMultiply,
+    NumpadParenLeft,
+    NumpadParenRight,
+    NumpadSubtract,
+    Escape,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
+    Fn,
+    PrintScreen,
+    ScrollLock,
+    Pause,
+    BrowserBack,
+    BrowserFavorites,
+    BrowserForward,
+    BrowserHome,
+    BrowserRefresh,
+    BrowserSearch,
+    BrowserStop,
+    Eject,
+    LaunchApp1,
+    LaunchApp2,
+    LaunchMail,
+    MediaPlayPause,
+    MediaSelect,
+    MediaStop,
+    MediaTrackNext,
+    MediaTrackPrevious,
+    Power,
+    Sleep,
+    AudioVolumeDown,
+    AudioVolumeMute,
+    AudioVolumeUp,
+    WakeUp,
+    Again,
+    Copy,
+    Cut,
+    Find,
+    Open,
+    Paste,
+    Props,
+    Select,
+    Undo,
+}
+
+impl fmt::Display for KeyCode {
+    /// the W3C name, or `Unidentified(scancode)`. Can be parsed back with `str::parse`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            KeyCode::Unidentified(scancode) => write!(f, "Unidentified({})", scancode),
+            _ => f.write_str(self.name()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// error returned when parsing an unknown key code name
+pub struct ParseKeyCodeError(pub String);
+
+impl fmt::Display for ParseKeyCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown key code {:?}", self.0)
+    }
+}
+
+impl FromStr for KeyCode {
+    type Err = ParseKeyCodeError;
+
+    /// parse a W3C key code name or `Unidentified(scancode)`. Also accepts the names previously
+    /// reported by the native target ("Snapshot", "Command", "LeftWin", "Colon")
+    fn from_str(s: &str) -> Result<KeyCode, ParseKeyCodeError> {
+        if let Some(scancode) = s
+            .strip_prefix("Unidentified(")
+            .and_then(|s| s.strip_suffix(')'))
+        {
+            return scancode
+                .parse()
+                .map(KeyCode::Unidentified)
+                .map_err(|_| ParseKeyCodeError(s.to_owned()));
+        }
+        match s {
+            "Unidentified" | "" => return Ok(KeyCode::Unidentified(0)),
+            "Snapshot" => return Ok(KeyCode::PrintScreen),
+            "Command" | "LeftWin" | "OSLeft" => return Ok(KeyCode::MetaLeft),
+            "OSRight" => return Ok(KeyCode::MetaRight),
+            "Colon" => return Ok(KeyCode::Semicolon),
+            _ => (),
+        }
+        KEY_CODES
+            .iter()
+            .find(|k| k.name() == s)
+            .cloned()
+            .ok_or_else(|| ParseKeyCodeError(s.to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for &key_code in KEY_CODES.iter().chain(&[
+            KeyCode::Unidentified(0),
+            KeyCode::Unidentified(42),
+            KeyCode::Unidentified(u32::MAX),
+        ]) {
+            assert_eq!(key_code.to_string().parse(), Ok(key_code));
+        }
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!("Unidentified".parse(), Ok(KeyCode::Unidentified(0)));
+        assert!("Unidentified(".parse::<KeyCode>().is_err());
+        assert!("Unidentified(-1)".parse::<KeyCode>().is_err());
+        assert!("KeyW ".parse::<KeyCode>().is_err());
+    }
+}
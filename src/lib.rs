@@ -20,6 +20,12 @@ extern crate glutin;
 #[cfg(not(target_arch = "wasm32"))]
 extern crate time;
 
+#[cfg(target_os = "linux")]
+extern crate libc;
+
+#[cfg(target_os = "linux")]
+extern crate x11_dl;
+
 #[cfg(not(target_arch = "wasm32"))]
 #[path = "native_app.rs"]
 /// main application struct
@@ -30,27 +36,136 @@ pub mod sys;
 /// filesystem api
 pub mod fs;
 
+/// rebindable input actions
+pub mod action;
+
+/// mouse cursor shapes
+pub mod cursor;
+
+/// gamepad api
+pub mod gamepad;
+
+/// trait based application callbacks
+pub mod handler;
+
+/// per-frame keyboard and mouse state
+pub mod input;
+
+/// typed keyboard key codes
+pub mod keycode;
+
+/// monitors and fullscreen modes
+pub mod monitor;
+
+/// input recording and deterministic replay
+mod record;
+
+pub use self::action::*;
+pub use self::cursor::*;
 pub use self::fs::*;
+pub use self::gamepad::*;
+pub use self::handler::*;
+pub use self::input::*;
+pub use self::keycode::*;
+pub use self::monitor::*;
 pub use self::sys::*;
 
+use std::path::PathBuf;
+
 /// game window configuration
 pub struct AppConfig {
     /// the window title (only visible on native target)
     pub title: String,
-    /// the window/canvas size in pixels
+    /// the window/canvas size in logical pixels (CSS pixels on web target), see `App.to_logical`
     pub size: (u32, u32),
     /// sync frames with screen frequency (can only be disabled on native target)
     pub vsync: bool,
     /// start the program without actually creating a window, for test purposes
     pub headless: bool,
-    /// start in full screen (native target only)
+    /// start the program without window, GL context nor display server, for CI machines.
+    /// The game loop, time and filesystem still work, but there is no input and
+    /// `App.canvas` resolves every GL symbol to null (native target). Also enabled by the `null-backend` cargo feature.
+    /// On web target, the canvas is only detached from the page : it receives no input, but `App.canvas`
+    /// is still a real canvas and can create a WebGL context
+    pub null_backend: bool,
+    /// start in full screen. Same as a `window_mode` of `WindowMode::Borderless { monitor: None }`.
+    /// On web target, the canvas goes fullscreen on the first user input, see `App.set_fullscreen`
     pub fullscreen: bool,
+    /// start windowed or fullscreen on a given monitor. The monitor is ignored on web target
+    pub window_mode: WindowMode,
     /// whether user can resize the window (native target only)
     pub resizable: bool,
-    /// whether the mouse cursor is visible while in the window
+    /// id or CSS selector of an existing canvas element to use (web target only).
+    /// None creates a new canvas at the end of the page body
+    pub canvas: Option<String>,
+    /// how the canvas size follows the page layout (web target only)
+    pub canvas_sizing: CanvasSizing,
+    /// which input events have their browser default action prevented (web target only)
+    pub prevent_default: PreventDefault,
+    /// receive the keyboard and paste events of the whole page instead of only when the canvas has the focus,
+    /// so that the keyboard still works after clicking elsewhere in the page (web target only)
+    pub listen_on_document: bool,
+    /// whether the mouse cursor is visible while in the window. Can be changed later with `App.set_cursor_visible`
     pub show_cursor: bool,
     /// whether clicking on the window close button exits the program or sends a CloseRequested event
     pub intercept_close_request: bool,
+    /// whether the primary touch also generates MousePos/MouseDown/MouseUp events (left button)
+    pub touch_to_mouse: bool,
+    /// record the events of every frame in this file, to replay them later with `replay`.
+    /// Can also be set with the `--record=path` command line parameter (`record=path` URL parameter on web).
+    /// New frames are written every second on native target. On web target, the file is stored in the
+    /// browser local storage (see [`FileSystem::write`]) when the page is hidden
+    pub record: Option<String>,
+    /// replay a file created with `record` instead of reading the real input. `now()` returns the recorded
+    /// frame time and gamepads are ignored. The game loop stops at the end of the recording (native target).
+    /// Can also be set with the `--replay=path` command line parameter (`replay=path` URL parameter on web)
+    pub replay: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// canvas size policy on web target. The canvas backing store is always the CSS size
+/// times `devicePixelRatio`, and `AppEvent::Resized` is sent when it changes
+pub enum CanvasSizing {
+    /// the CSS size is `AppConfig.size`
+    #[default]
+    Fixed,
+    /// the canvas fills its parent element.
+    /// The parent element needs a size that doesn't depend on its content
+    FillParent,
+    /// the canvas covers the whole browser window
+    FillWindow,
+    /// the largest size with the `AppConfig.size` aspect ratio fitting in the parent element
+    KeepAspectRatio,
+}
+
+#[derive(Default)]
+/// browser default action policy for the key, mouse button, mouse move and wheel events on web target.
+/// Touch events are always prevented, otherwise the browser also sends mouse events.
+/// The paste shortcut (ctrl-v) is never prevented so that the clipboard content is received
+pub enum PreventDefault {
+    /// prevent every default action : browser shortcuts (F5, ctrl-R, zoom, ...) and page scrolling
+    /// are disabled while the canvas has the focus
+    #[default]
+    All,
+    /// only prevent the default action of these keys. Other events keep their default action
+    Keys(Vec<KeyCode>),
+    /// prevent the default action when the function returns true
+    Custom(Box<dyn Fn(&AppEvent) -> bool>),
+}
+
+impl PreventDefault {
+    /// whether the default action of the browser event producing this event should be prevented
+    pub fn prevents(&self, event: &AppEvent) -> bool {
+        match *self {
+            PreventDefault::All => true,
+            PreventDefault::Keys(ref keys) => match *event {
+                AppEvent::KeyDown(ref e) => keys.contains(&e.key_code),
+                AppEvent::KeyUp(ref e) => keys.contains(&e.key_code),
+                _ => false,
+            },
+            PreventDefault::Custom(ref prevents) => prevents(event),
+        }
+    }
 }
 
 impl AppConfig {
@@ -60,10 +175,19 @@ impl AppConfig {
             size,
             vsync: true,
             headless: false,
+            null_backend: false,
             fullscreen: false,
+            window_mode: WindowMode::Windowed,
             resizable: true,
+            canvas: None,
+            canvas_sizing: CanvasSizing::Fixed,
+            prevent_default: PreventDefault::All,
+            listen_on_document: false,
             show_cursor: true,
             intercept_close_request: false,
+            touch_to_mouse: false,
+            record: None,
+            replay: None,
         }
     }
 }
@@ -71,23 +195,82 @@ impl AppConfig {
 /// keyboard and mouse events
 pub mod events {
     use std::fmt;
+    use std::path::PathBuf;
+    use KeyCode;
 
-    #[derive(Debug, Clone)]
+    #[derive(Debug, Clone, PartialEq)]
     /// data associated with a mouse button press/release event
     pub struct MouseButtonEvent {
         /// the button number (0=left, 1=middle, 2=right, ...)
         pub button: usize,
     }
 
-    #[derive(Clone)]
+    #[derive(Debug, Clone, PartialEq)]
+    /// data associated with a raw mouse motion event
+    pub struct MouseMotionEvent {
+        /// horizontal movement. Unit is device specific on native target, physical pixels on web target
+        pub dx: f64,
+        /// vertical movement. Unit is device specific on native target, physical pixels on web target
+        pub dy: f64,
+    }
+
+    #[derive(Debug, Clone, Default, PartialEq)]
+    /// data associated with a mouse wheel / touchpad scroll event.
+    /// Depending on the device, the scroll amount is either expressed in lines or in pixels,
+    /// the other pair of fields being 0.
+    /// Positive values mean scrolling up (away from the user) or to the right.
+    pub struct MouseWheelEvent {
+        /// horizontal scroll amount in lines
+        pub line_x: f32,
+        /// vertical scroll amount in lines
+        pub line_y: f32,
+        /// horizontal scroll amount in pixels
+        pub pixel_x: f64,
+        /// vertical scroll amount in pixels
+        pub pixel_y: f64,
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    /// data associated with a touch event
+    pub struct TouchEvent {
+        /// unique identifier of the finger, valid from TouchStart to TouchEnd/TouchCancel
+        pub id: u64,
+        /// touch position in pixels from the window top-left, like MousePos
+        pub pos: (f64, f64),
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    /// data associated with a file drop event
+    pub struct FileDroppedEvent {
+        /// the file name, without directory
+        pub name: String,
+        /// the file size in bytes
+        pub size: u64,
+        /// the file MIME type, like "image/png", if the browser knows it (web target only)
+        pub mime_type: Option<String>,
+        /// the full path of the file (native target only)
+        pub path: Option<PathBuf>,
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    /// data associated with an IME composition update, see `App.start_text_input`
+    pub struct TextCompositionEvent {
+        /// the text being composed (preedit). An empty text means the composition ended
+        pub text: String,
+        /// position of the cursor in the composed text, in characters
+        pub cursor: usize,
+    }
+
+    #[derive(Clone, PartialEq)]
     /// data associated with a key press event
-    /// Possible values for the scancode/virtual key code can be found in unrust/uni-app's `translate_scan_code`
-    /// [function](https://github.com/unrust/uni-app/blob/41246b070567e3267f128fff41ededf708149d60/src/native_keycode.rs#L160).
-    /// Warning, there are some slight variations from one OS to another, for example the `Command`, `F13`, `F14`, `F15` keys
+    /// Possible values for the scancode/virtual key code are the names of the [`KeyCode`] variants.
+    /// Warning, there are some slight variations from one OS to another, for example the `F13`, `F14`, `F15` keys
     /// only exist on Mac.
     pub struct KeyDownEvent {
         /// scancode : top left letter is "KeyQ" even on an azerty keyboard
         pub code: String,
+        /// typed version of `code`
+        pub key_code: KeyCode,
         /// virtual key code : top left letter is "KeyQ" on qwerty, "KeyA" on azerty
         pub key: String,
         /// whether a shift key is pressed
@@ -96,17 +279,22 @@ pub mod events {
         pub alt: bool,
         /// whether a control key is pressed
         pub ctrl: bool,
+        /// whether a logo key is pressed (Windows key, Command key on Mac)
+        pub logo: bool,
+        /// whether this event is an auto-repeat generated while the key is held down
+        pub repeat: bool,
     }
 
-    #[derive(Clone)]
+    #[derive(Clone, PartialEq)]
     /// data associated with a key release event
-    /// Possible values for the scancode/virtual key code can be found in unrust/uni-app's `translate_scan_code`
-    /// [function](https://github.com/unrust/uni-app/blob/41246b070567e3267f128fff41ededf708149d60/src/native_keycode.rs#L160).
-    /// Warning, there are some slight variations from one OS to another, for example the `Command`, `F13`, `F14`, `F15` keys
+    /// Possible values for the scancode/virtual key code are the names of the [`KeyCode`] variants.
+    /// Warning, there are some slight variations from one OS to another, for example the `F13`, `F14`, `F15` keys
     /// only exist on Mac.
     pub struct KeyUpEvent {
         /// scancode : top left letter is "KeyQ" even on an azerty keyboard
         pub code: String,
+        /// typed version of `code`
+        pub key_code: KeyCode,
         /// virtual key code : top left letter is "KeyQ" on qwerty, "KeyA" on azerty
         pub key: String,
         /// whether a shift key is pressed
@@ -115,18 +303,24 @@ pub mod events {
         pub alt: bool,
         /// whether a control key is pressed
         pub ctrl: bool,
+        /// whether a logo key is pressed (Windows key, Command key on Mac)
+        pub logo: bool,
+        /// whether this event is an auto-repeat generated while the key is held down
+        pub repeat: bool,
     }
 
     impl fmt::Debug for KeyUpEvent {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
             write!(
                 f,
-                "{} {} {} {} {}",
+                "{} {} {} {} {} {} {}",
                 if self.shift { "shift" } else { "" },
                 if self.alt { "alt" } else { "" },
                 if self.ctrl { "ctrl" } else { "" },
+                if self.logo { "logo" } else { "" },
                 self.code,
                 self.key,
+                if self.repeat { "repeat" } else { "" },
             )
         }
     }
@@ -135,12 +329,14 @@ pub mod events {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
             write!(
                 f,
-                "{} {} {} {} {}",
+                "{} {} {} {} {} {} {}",
                 if self.shift { "shift" } else { "" },
                 if self.alt { "alt" } else { "" },
                 if self.ctrl { "ctrl" } else { "" },
+                if self.logo { "logo" } else { "" },
                 self.code,
                 self.key,
+                if self.repeat { "repeat" } else { "" },
             )
         }
     }
@@ -148,25 +344,115 @@ pub mod events {
 
 pub use events::*;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 /// window event types
+///
+/// All positions and sizes are in physical pixels on both targets : window (native) or canvas backing
+/// store (web) pixels, from the top-left corner. A position can be compared to the `Resized` size or
+/// used as a GL viewport coordinate directly. Use `App.to_logical` to get the CSS pixels
+/// (web) or logical pixels (native) used for layout, and `App.hidpi_factor` for the ratio between both.
 pub enum AppEvent {
     /// mouse button press
     MouseDown(MouseButtonEvent),
     /// mouse button release
     MouseUp(MouseButtonEvent),
+    /// mouse wheel or touchpad scroll
+    MouseWheel(MouseWheelEvent),
     /// keyboard press
     KeyDown(KeyDownEvent),
     /// keyboard release
     KeyUp(KeyUpEvent),
     /// text input events
     CharEvent(char),
-    /// window resize
+    /// the text being composed with an input method (IME) changed. Only sent between
+    /// `App.start_text_input` and `App.stop_text_input` (web target only)
+    TextComposition(TextCompositionEvent),
+    /// text entered between `App.start_text_input` and `App.stop_text_input`, including
+    /// composed characters and input method results. Control characters are not included
+    TextCommit(String),
+    /// window (native) or canvas backing store (web) resize
     Resized((u32, u32)),
     /// mouse cursor position in pixels from the window top-left
     MousePos((f64, f64)),
-    /// a file has been dropped on the game window. Get it with `App.get_dropped_file`
-    FileDropped(String),
+    /// raw mouse movement, not limited by the window borders. Use it with `App.set_cursor_grab`
+    MouseMotion(MouseMotionEvent),
+    /// the cursor has been grabbed (true) or released (false), see `App.set_cursor_grab`
+    PointerLockChanged(bool),
+    /// the window (native) or canvas (web) entered (true) or left (false) fullscreen,
+    /// see `App.set_fullscreen`
+    FullscreenChanged(bool),
+    /// a finger touched the screen
+    TouchStart(TouchEvent),
+    /// a finger moved on the screen
+    TouchMove(TouchEvent),
+    /// a finger was lifted from the screen
+    TouchEnd(TouchEvent),
+    /// the system cancelled a touch (for example because the window lost focus)
+    TouchCancel(TouchEvent),
+    /// files are dragged over the game window. Sent for each file with its path on native target.
+    /// On web target, the files are only known once dropped : sent once with None
+    FileHovered(Option<PathBuf>),
+    /// the hovered files left the game window without being dropped
+    FileHoverCancelled,
+    /// a file has been dropped on the game window. Get its content with `App.get_dropped_file`,
+    /// which returns the files in the same order as these events
+    FileDropped(FileDroppedEvent),
     /// window close button was pressed and [`AppConfig.intercept_close_request`] is true
     CloseRequested,
+    /// the window (native) or canvas (web) received the keyboard focus
+    FocusGained,
+    /// the window (native) or canvas (web) lost the keyboard focus. All keys and buttons are considered released
+    FocusLost,
+    /// the mouse cursor entered the window
+    CursorEntered,
+    /// the mouse cursor left the window
+    CursorLeft,
+    /// window position in pixels from the screen top-left. Only sent on native target
+    Moved((i32, i32)),
+    /// the HiDPI factor changed, for example when the window was moved to another monitor
+    ScaleFactorChanged(f32),
+    /// the clipboard content, in response to `App.request_clipboard_text`.
+    /// On web target, also sent when the user pastes text in the canvas (ctrl-v)
+    ClipboardText(String),
+    /// a gamepad has been plugged. Contains the gamepad id
+    GamepadConnected(usize),
+    /// a gamepad has been unplugged. Contains the gamepad id
+    GamepadDisconnected(usize),
+    /// gamepad button press
+    GamepadButtonDown(GamepadButtonEvent),
+    /// gamepad button release
+    GamepadButtonUp(GamepadButtonEvent),
+    /// gamepad stick or trigger motion
+    GamepadAxis(GamepadAxisEvent),
+}
+
+#[derive(Default)]
+/// turns the primary touch (the first finger down when no other finger is touching)
+/// into left mouse button events, see [`AppConfig.touch_to_mouse`]
+pub(crate) struct TouchMouseEmulation {
+    primary: Option<u64>,
+}
+
+impl TouchMouseEmulation {
+    /// push the mouse events matching the touch event `evt`, if any
+    pub(crate) fn translate(&mut self, evt: &AppEvent, events: &mut Vec<AppEvent>) {
+        match *evt {
+            AppEvent::TouchStart(ref t) if self.primary.is_none() => {
+                self.primary = Some(t.id);
+                events.push(AppEvent::MousePos(t.pos));
+                events.push(AppEvent::MouseDown(MouseButtonEvent { button: 0 }));
+            }
+            AppEvent::TouchMove(ref t) if self.primary == Some(t.id) => {
+                events.push(AppEvent::MousePos(t.pos));
+            }
+            AppEvent::TouchEnd(ref t) | AppEvent::TouchCancel(ref t)
+                if self.primary == Some(t.id) =>
+            {
+                self.primary = None;
+                events.push(AppEvent::MousePos(t.pos));
+                events.push(AppEvent::MouseUp(MouseButtonEvent { button: 0 }));
+            }
+            _ => (),
+        }
+    }
 }
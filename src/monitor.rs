@@ -0,0 +1,39 @@
+#[derive(Debug, Clone)]
+/// a display monitor, see `App.monitors`
+pub struct Monitor {
+    /// the platform name of the monitor, if any
+    pub name: Option<String>,
+    /// position of the monitor top-left on the desktop, in physical pixels
+    pub position: (i32, i32),
+    /// current resolution in physical pixels
+    pub size: (u32, u32),
+    /// hidpi factor of the monitor
+    pub scale_factor: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// how the window uses the screen, see `App.set_window_mode`.
+/// `monitor` is an index in `App.monitors`. None uses the monitor containing the window
+/// (the primary monitor when creating the window)
+pub enum WindowMode {
+    #[default]
+    Windowed,
+    /// a borderless window covering the whole monitor, using the desktop resolution.
+    /// Changing the monitor resolution is not supported
+    Borderless { monitor: Option<usize> },
+}
+
+impl WindowMode {
+    /// whether the window covers a whole monitor
+    pub fn is_fullscreen(&self) -> bool {
+        *self != WindowMode::Windowed
+    }
+
+    /// the monitor index for fullscreen modes
+    pub fn monitor(&self) -> Option<usize> {
+        match *self {
+            WindowMode::Windowed => None,
+            WindowMode::Borderless { monitor } => monitor,
+        }
+    }
+}
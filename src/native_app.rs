@@ -1,24 +1,47 @@
+mod native_clipboard;
+mod native_cursor;
+mod native_gamepad;
 mod native_keycode;
+mod native_layout;
 
 use glutin;
-use glutin::{ElementState, Event, MouseButton, WindowEvent};
-use std::cell::RefCell;
+use glutin::{
+    DeviceEvent, ElementState, Event, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent,
+};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashSet, VecDeque};
 use std::env;
+use std::fs;
 use std::os::raw::c_void;
 use std::process;
+use std::ptr;
 use std::rc::Rc;
 use time;
 
 use AppConfig;
 use AppEvent;
 
-use self::native_keycode::{translate_scan_code, translate_virtual_key};
+use self::native_clipboard::Clipboard;
+use self::native_cursor::CustomCursor;
+use self::native_gamepad::GamepadManager;
+use self::native_keycode::{scan_code, translate_scan_code, translate_virtual_key};
+use self::native_layout::KeyboardLayout;
+use crate::cursor::check_cursor_image;
 use crate::events;
-use crate::{File, FileSystem};
+use crate::handler::{exit_requested, handle_frame, set_exit_requested, FnHandler};
+use crate::keycode::KEY_CODES;
+use crate::record::{get_param, replay_time, Recorder, Replay};
+use crate::File;
+use crate::{
+    AppHandler, CursorIcon, GamepadState, InputState, KeyCode, Monitor, TouchMouseEmulation,
+    WindowMode,
+};
 
 enum WindowContext {
-    Normal(glutin::GlWindow),
+    Normal(Box<glutin::GlWindow>),
     Headless(glutin::HeadlessContext),
+    /// null backend : no window and no GL context
+    Null,
 }
 
 impl WindowContext {
@@ -36,10 +59,11 @@ impl WindowContext {
         }
     }
 
-    fn context(&self) -> &dyn glutin::GlContext {
+    fn context(&self) -> Option<&dyn glutin::GlContext> {
         match self {
-            WindowContext::Normal(ref w) => w,
-            WindowContext::Headless(ref w) => w,
+            WindowContext::Normal(ref w) => Some(w.as_ref()),
+            WindowContext::Headless(ref w) => Some(w),
+            WindowContext::Null => None,
         }
     }
 
@@ -47,7 +71,7 @@ impl WindowContext {
         use glutin::GlContext;
         match self {
             WindowContext::Normal(ref w) => w.swap_buffers(),
-            WindowContext::Headless(_) => Ok(()),
+            WindowContext::Headless(_) | WindowContext::Null => Ok(()),
         }
     }
 }
@@ -55,31 +79,58 @@ impl WindowContext {
 /// the main application struct
 pub struct App {
     window: WindowContext,
-    events_loop: glutin::EventsLoop,
-    exiting: bool,
+    /// size of the render target without window (headless and null backends)
+    size: (u32, u32),
+    /// None with the null backend
+    events_loop: Option<glutin::EventsLoop>,
     intercept_close_request: bool,
     pub events: Rc<RefCell<Vec<AppEvent>>>,
-    dropped_files: Vec<File>,
+    dropped_files: VecDeque<File>,
+    touch_emulation: Option<TouchMouseEmulation>,
+    gamepads: GamepadManager,
+    clipboard: Clipboard,
+    custom_cursor: CustomCursor,
+    keyboard_layout: KeyboardLayout,
+    cursor_visible: bool,
+    fullscreen: Cell<bool>,
+    /// whether TextCommit events are sent
+    text_input: bool,
+    /// scancodes of the keys currently down, to detect auto-repeat
+    pressed_keys: HashSet<glutin::ScanCode>,
+    input: InputState,
+    focused: bool,
+    /// events added with `inject_event`, delivered on next frame
+    injected: Vec<AppEvent>,
+    recorder: Option<Recorder>,
+    replay: Option<Replay>,
 }
 
 fn get_virtual_key(input: glutin::KeyboardInput) -> String {
     match input.virtual_keycode {
-        Some(k) => {
-            let mut s = translate_virtual_key(k).into();
-            if s == "" {
-                s = format!("{:?}", k);
-            }
-            s
-        }
+        Some(k) => match translate_virtual_key(k) {
+            KeyCode::Unidentified(_) => format!("{:?}", k),
+            key_code => key_code.to_string(),
+        },
         None => "".into(),
     }
 }
 
-fn get_scan_code(input: glutin::KeyboardInput) -> String {
-    translate_scan_code(input.scancode).into()
+fn to_monitor(monitor: glutin::MonitorId) -> Monitor {
+    let size = monitor.get_dimensions().into();
+    Monitor {
+        name: monitor.get_name(),
+        position: monitor.get_position().into(),
+        size,
+        scale_factor: monitor.get_hidpi_factor() as f32,
+    }
 }
 
-fn translate_event(e: glutin::Event, dpi_factor: f32) -> Option<AppEvent> {
+fn translate_event(
+    e: glutin::Event,
+    dpi_factor: f32,
+    pressed_keys: &mut HashSet<glutin::ScanCode>,
+    focused: bool,
+) -> Option<AppEvent> {
     if let Event::WindowEvent {
         event: winevent, ..
     } = e
@@ -98,54 +149,180 @@ fn translate_event(e: glutin::Event, dpi_factor: f32) -> Option<AppEvent> {
                     ElementState::Released => Some(AppEvent::MouseUp(event)),
                 }
             }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let event = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => events::MouseWheelEvent {
+                        line_x: x,
+                        line_y: y,
+                        ..Default::default()
+                    },
+                    MouseScrollDelta::PixelDelta(position) => {
+                        let phys = glutin::dpi::PhysicalPosition::from_logical(
+                            position,
+                            f64::from(dpi_factor),
+                        );
+                        events::MouseWheelEvent {
+                            pixel_x: phys.x,
+                            pixel_y: phys.y,
+                            ..Default::default()
+                        }
+                    }
+                };
+                Some(AppEvent::MouseWheel(event))
+            }
             WindowEvent::CursorMoved { position, .. } => {
                 let phys =
                     glutin::dpi::PhysicalPosition::from_logical(position, f64::from(dpi_factor));
                 Some(AppEvent::MousePos(phys.into()))
             }
-            WindowEvent::KeyboardInput { input, .. } => match input.state {
-                ElementState::Pressed => Some(AppEvent::KeyDown(events::KeyDownEvent {
-                    key: get_virtual_key(input),
-                    code: get_scan_code(input),
-                    shift: input.modifiers.shift,
-                    alt: input.modifiers.alt,
-                    ctrl: input.modifiers.ctrl,
-                })),
-                ElementState::Released => Some(AppEvent::KeyUp(events::KeyUpEvent {
-                    key: get_virtual_key(input),
-                    code: get_scan_code(input),
-                    shift: input.modifiers.shift,
-                    alt: input.modifiers.alt,
-                    ctrl: input.modifiers.ctrl,
-                })),
-            },
+            WindowEvent::Touch(touch) => {
+                let phys = glutin::dpi::PhysicalPosition::from_logical(
+                    touch.location,
+                    f64::from(dpi_factor),
+                );
+                let event = events::TouchEvent {
+                    id: touch.id,
+                    pos: phys.into(),
+                };
+                Some(match touch.phase {
+                    TouchPhase::Started => AppEvent::TouchStart(event),
+                    TouchPhase::Moved => AppEvent::TouchMove(event),
+                    TouchPhase::Ended => AppEvent::TouchEnd(event),
+                    TouchPhase::Cancelled => AppEvent::TouchCancel(event),
+                })
+            }
+            WindowEvent::KeyboardInput { input, .. } => {
+                let key_code = translate_scan_code(input.scancode, input.virtual_keycode);
+                match input.state {
+                    ElementState::Pressed => Some(AppEvent::KeyDown(events::KeyDownEvent {
+                        key: get_virtual_key(input),
+                        code: key_code.name().to_owned(),
+                        key_code,
+                        shift: input.modifiers.shift,
+                        alt: input.modifiers.alt,
+                        ctrl: input.modifiers.ctrl,
+                        logo: input.modifiers.logo,
+                        // a press event for a key that is already down
+                        repeat: !pressed_keys.insert(input.scancode),
+                    })),
+                    ElementState::Released => {
+                        pressed_keys.remove(&input.scancode);
+                        Some(AppEvent::KeyUp(events::KeyUpEvent {
+                            key: get_virtual_key(input),
+                            code: key_code.name().to_owned(),
+                            key_code,
+                            shift: input.modifiers.shift,
+                            alt: input.modifiers.alt,
+                            ctrl: input.modifiers.ctrl,
+                            logo: input.modifiers.logo,
+                            repeat: false,
+                        }))
+                    }
+                }
+            }
             WindowEvent::ReceivedCharacter(c) => Some(AppEvent::CharEvent(c)),
+            WindowEvent::Focused(true) => Some(AppEvent::FocusGained),
+            WindowEvent::Focused(false) => {
+                // key release events are lost while the window is not focused
+                pressed_keys.clear();
+                Some(AppEvent::FocusLost)
+            }
+            WindowEvent::CursorEntered { .. } => Some(AppEvent::CursorEntered),
+            WindowEvent::CursorLeft { .. } => Some(AppEvent::CursorLeft),
+            WindowEvent::Moved(position) => {
+                let phys =
+                    glutin::dpi::PhysicalPosition::from_logical(position, f64::from(dpi_factor));
+                Some(AppEvent::Moved((phys.x as i32, phys.y as i32)))
+            }
+            WindowEvent::HiDpiFactorChanged(factor) => {
+                Some(AppEvent::ScaleFactorChanged(factor as f32))
+            }
             WindowEvent::Resized(size) => {
                 let phys = glutin::dpi::PhysicalSize::from_logical(size, f64::from(dpi_factor));
                 Some(AppEvent::Resized(phys.into()))
             }
             WindowEvent::CloseRequested => Some(AppEvent::CloseRequested),
+            WindowEvent::HoveredFile(path) => Some(AppEvent::FileHovered(Some(path))),
+            WindowEvent::HoveredFileCancelled => Some(AppEvent::FileHoverCancelled),
             WindowEvent::DroppedFile(path) => {
-                Some(AppEvent::FileDropped(path.to_str().unwrap().to_owned()))
+                Some(AppEvent::FileDropped(events::FileDroppedEvent {
+                    name: path
+                        .file_name()
+                        .map_or_else(String::new, |n| n.to_string_lossy().into_owned()),
+                    size: fs::metadata(&path).map(|m| m.len()).unwrap_or(0),
+                    mime_type: None,
+                    path: Some(path),
+                }))
             }
             _ => None,
         }
+    } else if let Event::DeviceEvent {
+        event: DeviceEvent::MouseMotion { delta: (dx, dy) },
+        ..
+    } = e
+    {
+        // device events are also received when the window is in background
+        if focused {
+            Some(AppEvent::MouseMotion(events::MouseMotionEvent { dx, dy }))
+        } else {
+            None
+        }
     } else {
         None
     }
 }
 
+fn translate_cursor(icon: CursorIcon) -> glutin::MouseCursor {
+    use glutin::MouseCursor;
+    match icon {
+        CursorIcon::Default => MouseCursor::Default,
+        CursorIcon::Text => MouseCursor::Text,
+        CursorIcon::Hand => MouseCursor::Hand,
+        CursorIcon::Crosshair => MouseCursor::Crosshair,
+        CursorIcon::Wait => MouseCursor::Wait,
+        CursorIcon::Progress => MouseCursor::Progress,
+        CursorIcon::Help => MouseCursor::Help,
+        CursorIcon::Move => MouseCursor::Move,
+        CursorIcon::NotAllowed => MouseCursor::NotAllowed,
+        CursorIcon::Grab => MouseCursor::Grab,
+        CursorIcon::Grabbing => MouseCursor::Grabbing,
+        CursorIcon::EResize => MouseCursor::EResize,
+        CursorIcon::NResize => MouseCursor::NResize,
+        CursorIcon::NeResize => MouseCursor::NeResize,
+        CursorIcon::NwResize => MouseCursor::NwResize,
+        CursorIcon::SResize => MouseCursor::SResize,
+        CursorIcon::SeResize => MouseCursor::SeResize,
+        CursorIcon::SwResize => MouseCursor::SwResize,
+        CursorIcon::WResize => MouseCursor::WResize,
+        CursorIcon::EwResize => MouseCursor::EwResize,
+        CursorIcon::NsResize => MouseCursor::NsResize,
+        CursorIcon::NeswResize => MouseCursor::NeswResize,
+        CursorIcon::NwseResize => MouseCursor::NwseResize,
+    }
+}
+
 impl App {
     /// create a new game window
     pub fn new(config: AppConfig) -> App {
         use glutin::*;
-        let events_loop = glutin::EventsLoop::new();
+        // creating the events loop already requires a display server
+        let null_backend = config.null_backend || cfg!(feature = "null-backend");
+        let events_loop = if null_backend {
+            None
+        } else {
+            Some(glutin::EventsLoop::new())
+        };
         let gl_req = GlRequest::GlThenGles {
             opengl_version: (3, 2),
             opengles_version: (2, 0),
         };
 
-        let window = if config.headless {
+        let fullscreen = (config.fullscreen || config.window_mode.is_fullscreen())
+            && !null_backend
+            && !config.headless;
+        let window = if null_backend {
+            WindowContext::Null
+        } else if config.headless {
             let context = glutin::HeadlessRendererBuilder::new(config.size.0, config.size.1)
                 .with_gl(gl_req)
                 .with_gl_profile(GlProfile::Core)
@@ -154,8 +331,20 @@ impl App {
 
             WindowContext::Headless(context)
         } else {
-            let monitor = if config.fullscreen {
-                events_loop.get_available_monitors().nth(0)
+            let events_loop = events_loop.as_ref().unwrap();
+            let window_mode = if config.fullscreen && !config.window_mode.is_fullscreen() {
+                WindowMode::Borderless { monitor: None }
+            } else {
+                config.window_mode
+            };
+            let monitor = if window_mode.is_fullscreen() {
+                Some(match window_mode.monitor() {
+                    Some(index) => events_loop
+                        .get_available_monitors()
+                        .nth(index)
+                        .unwrap_or_else(|| events_loop.get_primary_monitor()),
+                    None => events_loop.get_primary_monitor(),
+                })
             } else {
                 None
             };
@@ -164,6 +353,7 @@ impl App {
                 .with_title(config.title)
                 .with_fullscreen(monitor)
                 .with_resizable(config.resizable)
+                .with_multitouch()
                 .with_dimensions((config.size.0, config.size.1).into());
 
             let context = glutin::ContextBuilder::new()
@@ -171,26 +361,61 @@ impl App {
                 .with_gl(gl_req)
                 .with_gl_profile(GlProfile::Core);
 
-            let gl_window = glutin::GlWindow::new(window, context, &events_loop).unwrap();
+            let gl_window = glutin::GlWindow::new(window, context, events_loop).unwrap();
 
             if !config.show_cursor {
                 gl_window.hide_cursor(true);
             }
 
-            WindowContext::Normal(gl_window)
+            WindowContext::Normal(Box::new(gl_window))
         };
 
-        unsafe {
-            window.context().make_current().unwrap();
+        if let Some(context) = window.context() {
+            unsafe {
+                context.make_current().unwrap();
+            }
         }
 
+        let params = App::get_params();
+        set_exit_requested(false);
+
         App {
             window,
             events_loop,
-            exiting: false,
             intercept_close_request: config.intercept_close_request,
+            size: config.size,
             events: Rc::new(RefCell::new(Vec::new())),
-            dropped_files: Vec::new(),
+            dropped_files: VecDeque::new(),
+            touch_emulation: if config.touch_to_mouse {
+                Some(TouchMouseEmulation::default())
+            } else {
+                None
+            },
+            gamepads: GamepadManager::new(),
+            clipboard: Clipboard::new(),
+            custom_cursor: CustomCursor::new(),
+            keyboard_layout: KeyboardLayout::new(),
+            cursor_visible: config.show_cursor,
+            fullscreen: Cell::new(fullscreen),
+            text_input: false,
+            pressed_keys: HashSet::new(),
+            input: InputState::default(),
+            focused: true,
+            injected: Vec::new(),
+            recorder: config
+                .record
+                .or_else(|| get_param(&params, "record"))
+                .map(|path| Recorder::new(&path, now())),
+            replay: config
+                .replay
+                .or_else(|| get_param(&params, "replay"))
+                .and_then(|path| match Replay::open(&path) {
+                    Ok(replay) => Some(replay),
+                    Err(e) => {
+                        App::print(format!("Could not open the recording {} : {}\n", path, e));
+                        None
+                    }
+                }),
         }
     }
 
@@ -214,25 +439,243 @@ impl App {
         params
     }
 
-    /// activate or deactivate fullscreen. only works on native target
+    /// activate or deactivate fullscreen. An `AppEvent::FullscreenChanged` event is sent
     pub fn set_fullscreen(&self, b: bool) {
+        self.set_window_mode(if b {
+            WindowMode::Borderless { monitor: None }
+        } else {
+            WindowMode::Windowed
+        });
+    }
+
+    /// switch between windowed and fullscreen on a given monitor
+    pub fn set_window_mode(&self, mode: WindowMode) {
         if let WindowContext::Normal(ref glwindow) = self.window {
-            if b {
-                glwindow.set_fullscreen(Some(glwindow.window().get_current_monitor()));
+            if mode.is_fullscreen() {
+                let monitor = mode
+                    .monitor()
+                    .and_then(|index| glwindow.get_available_monitors().nth(index))
+                    .unwrap_or_else(|| glwindow.get_current_monitor());
+                glwindow.set_fullscreen(Some(monitor));
             } else {
                 glwindow.set_fullscreen(None);
             }
+            if self.fullscreen.replace(mode.is_fullscreen()) != mode.is_fullscreen() {
+                self.events
+                    .borrow_mut()
+                    .push(AppEvent::FullscreenChanged(mode.is_fullscreen()));
+            }
+        }
+    }
+
+    /// whether the window is fullscreen
+    pub fn is_fullscreen(&self) -> bool {
+        self.fullscreen.get()
+    }
+
+    /// the connected monitors. Empty without window
+    pub fn monitors(&self) -> Vec<Monitor> {
+        match self.window {
+            WindowContext::Normal(ref glwindow) => {
+                glwindow.get_available_monitors().map(to_monitor).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// index in `monitors` of the monitor containing the window
+    pub fn current_monitor(&self) -> Option<usize> {
+        match self.window {
+            WindowContext::Normal(ref glwindow) => {
+                let current = to_monitor(glwindow.get_current_monitor());
+                self.monitors()
+                    .iter()
+                    .position(|m| m.name == current.name && m.position == current.position)
+            }
+            _ => None,
+        }
+    }
+
+    /// change the window title
+    pub fn set_title(&self, title: &str) {
+        if let WindowContext::Normal(ref glwindow) = self.window {
+            glwindow.set_title(title);
+        }
+    }
+
+    /// size of the window client area in pixels, as in `AppEvent::Resized`
+    pub fn inner_size(&self) -> (u32, u32) {
+        match self.window {
+            WindowContext::Normal(ref glwindow) => glwindow
+                .get_inner_size()
+                .map(|size| size.to_physical(glwindow.get_hidpi_factor()).into())
+                .unwrap_or((0, 0)),
+            _ => self.size,
+        }
+    }
+
+    /// resize the window client area, in pixels. An `AppEvent::Resized` event is sent
+    pub fn set_inner_size(&self, size: (u32, u32)) {
+        if let WindowContext::Normal(ref glwindow) = self.window {
+            glwindow.set_inner_size(self.to_logical_size(size));
+        }
+    }
+
+    /// position of the window top-left corner, including the decorations, in pixels from the
+    /// desktop top-left. None if the window was closed or the platform doesn't support it
+    pub fn outer_position(&self) -> Option<(i32, i32)> {
+        match self.window {
+            WindowContext::Normal(ref glwindow) => glwindow
+                .get_position()
+                .map(|pos| pos.to_physical(glwindow.get_hidpi_factor()).into()),
+            _ => None,
+        }
+    }
+
+    /// move the window, see `outer_position`
+    pub fn set_outer_position(&self, pos: (i32, i32)) {
+        if let WindowContext::Normal(ref glwindow) = self.window {
+            let pos = glutin::dpi::PhysicalPosition::from(pos);
+            glwindow.set_position(pos.to_logical(glwindow.get_hidpi_factor()));
+        }
+    }
+
+    /// minimum size of the window client area in pixels when the user resizes it.
+    /// None removes the constraint
+    pub fn set_min_size(&self, size: Option<(u32, u32)>) {
+        if let WindowContext::Normal(ref glwindow) = self.window {
+            glwindow.set_min_dimensions(size.map(|size| self.to_logical_size(size)));
+        }
+    }
+
+    /// maximum size of the window client area in pixels when the user resizes it.
+    /// None removes the constraint
+    pub fn set_max_size(&self, size: Option<(u32, u32)>) {
+        if let WindowContext::Normal(ref glwindow) = self.window {
+            glwindow.set_max_dimensions(size.map(|size| self.to_logical_size(size)));
+        }
+    }
+
+    /// whether the user can resize the window
+    pub fn set_resizable(&self, resizable: bool) {
+        if let WindowContext::Normal(ref glwindow) = self.window {
+            glwindow.set_resizable(resizable);
+        }
+    }
+
+    /// show or hide the window
+    pub fn set_visible(&self, visible: bool) {
+        if let WindowContext::Normal(ref glwindow) = self.window {
+            if visible {
+                glwindow.show();
+            } else {
+                glwindow.hide();
+            }
+        }
+    }
+
+    fn to_logical_size(&self, size: (u32, u32)) -> glutin::dpi::LogicalSize {
+        glutin::dpi::PhysicalSize::from(size).to_logical(f64::from(self.hidpi_factor()))
+    }
+
+    /// confine the mouse cursor to the window, for first-person controls.
+    /// Use `AppEvent::MouseMotion` to get the mouse movements while the cursor is grabbed.
+    pub fn set_cursor_grab(&self, grab: bool) {
+        if let WindowContext::Normal(ref glwindow) = self.window {
+            match glwindow.grab_cursor(grab) {
+                Ok(()) => self
+                    .events
+                    .borrow_mut()
+                    .push(AppEvent::PointerLockChanged(grab)),
+                Err(msg) => App::print(format!("Could not grab the cursor : {}\n", msg)),
+            }
+        }
+    }
+
+    /// change the mouse cursor shape
+    pub fn set_cursor(&mut self, icon: CursorIcon) {
+        if let WindowContext::Normal(ref glwindow) = self.window {
+            glwindow.set_cursor(translate_cursor(icon));
+            self.custom_cursor.clear();
+        }
+    }
+
+    /// use an image as mouse cursor. `rgba` contains `width` x `height` pixels, 4 bytes per pixel,
+    /// else the image is ignored.
+    /// `hotspot` is the position of the click point in the image.
+    /// Only supported on X11 for native target
+    pub fn set_custom_cursor(&mut self, rgba: &[u8], width: u32, height: u32, hotspot: (u32, u32)) {
+        if let Err(msg) = check_cursor_image(rgba, width, height) {
+            App::print(format!("Could not set the cursor : {}\n", msg));
+            return;
+        }
+        if let WindowContext::Normal(ref glwindow) = self.window {
+            match self
+                .custom_cursor
+                .set(glwindow.window(), rgba, width, height, hotspot)
+            {
+                Ok(()) if self.cursor_visible => self.custom_cursor.show(),
+                Ok(()) => (),
+                Err(msg) => App::print(format!("Could not set the cursor : {}\n", msg)),
+            }
         }
     }
 
+    /// show or hide the mouse cursor while it is in the window
+    pub fn set_cursor_visible(&mut self, visible: bool) {
+        if let WindowContext::Normal(ref glwindow) = self.window {
+            glwindow.hide_cursor(!visible);
+            if visible {
+                self.custom_cursor.show();
+            }
+            self.cursor_visible = visible;
+        }
+    }
+
+    /// start receiving `AppEvent::TextCommit` events, for example when a text field gets the focus.
+    /// The input method of the system is handled by the window manager on native target
+    pub fn start_text_input(&mut self) {
+        self.text_input = true;
+    }
+
+    /// stop receiving `AppEvent::TextCommit` events
+    pub fn stop_text_input(&mut self) {
+        self.text_input = false;
+    }
+
+    /// position of the input method candidate window, in pixels from the window top-left.
+    /// Typically the text cursor position in the edited text field
+    pub fn set_ime_position(&self, pos: (f64, f64)) {
+        if let WindowContext::Normal(ref glwindow) = self.window {
+            let dpi_factor = f64::from(self.hidpi_factor());
+            glwindow.set_ime_spot(glutin::dpi::PhysicalPosition::from(pos).to_logical(dpi_factor));
+        }
+    }
+
+    /// copy a text to the system clipboard.
+    /// Only linux (X11) has a system clipboard for now : on other operating systems,
+    /// the text is only available to this application
+    pub fn set_clipboard_text(&self, text: &str) {
+        self.clipboard.set_text(text);
+    }
+
+    /// ask for the system clipboard content. It is received later in an `AppEvent::ClipboardText` event.
+    /// Only linux (X11) has a system clipboard for now, see `set_clipboard_text`
+    pub fn request_clipboard_text(&self) {
+        self.clipboard.request_text();
+    }
+
     /// print a message on standard output (native) or js console (web)
     pub fn print<T: Into<String>>(msg: T) {
         print!("{}", msg.into());
     }
 
-    /// exit current process (close the game window). On web target, this does nothing.
+    /// stop the game loop at the end of the current frame. This function returns immediately : the
+    /// rest of the frame still runs. `run_handler` then calls `AppHandler::exit` and returns, `run` exits
+    /// the current process and `poll_events` returns false.
+    /// On web target, the animation frame loop stops and `AppHandler::exit` is called
     pub fn exit() {
-        process::exit(0);
+        set_exit_requested(true);
     }
 
     /// returns the HiDPI factor for current screen
@@ -240,11 +683,26 @@ impl App {
         self.window.hidpi_factor()
     }
 
+    /// convert a position in physical pixels (as in the events) to logical pixels
+    pub fn to_logical(&self, pos: (f64, f64)) -> (f64, f64) {
+        let factor = f64::from(self.hidpi_factor());
+        (pos.0 / factor, pos.1 / factor)
+    }
+
+    /// convert a position in logical pixels to physical pixels, see `to_logical`
+    pub fn to_physical(&self, pos: (f64, f64)) -> (f64, f64) {
+        let factor = f64::from(self.hidpi_factor());
+        (pos.0 * factor, pos.1 * factor)
+    }
+
     fn get_proc_address(&self, name: &str) -> *const c_void {
-        self.window.context().get_proc_address(name) as *const c_void
+        match self.window.context() {
+            Some(context) => context.get_proc_address(name) as *const c_void,
+            None => ptr::null(),
+        }
     }
 
-    /// return the opengl context for this window
+    /// return the opengl context for this window. With the null backend, every symbol resolves to null
     pub fn canvas<'p>(&'p self) -> Box<dyn 'p + FnMut(&str) -> *const c_void> {
         Box::new(move |name| self.get_proc_address(name))
     }
@@ -254,60 +712,173 @@ impl App {
         let mut running = true;
 
         let dpi_factor = self.hidpi_factor();
-        let (window, events_loop, events, dropped_files) = (
+        let (window, events_loop, events, dropped_files, touch_emulation, pressed_keys, focused) = (
             &self.window,
-            &mut self.events_loop,
-            &mut self.events,
+            self.events_loop.as_mut(),
+            &self.events,
             &mut self.dropped_files,
+            &mut self.touch_emulation,
+            &mut self.pressed_keys,
+            &mut self.focused,
         );
         let intercept_close_request = self.intercept_close_request;
-        events_loop.poll_events(|event| {
-            match event {
-                glutin::Event::WindowEvent { ref event, .. } => match event {
-                    &glutin::WindowEvent::CloseRequested => {
-                        if !intercept_close_request {
+        let text_input = self.text_input;
+        // the null backend has no event source
+        if let Some(events_loop) = events_loop {
+            events_loop.poll_events(|event| {
+                if let glutin::Event::WindowEvent { ref event, .. } = event {
+                    match *event {
+                        glutin::WindowEvent::CloseRequested if !intercept_close_request => {
                             running = false;
                         }
-                    }
-                    &glutin::WindowEvent::Resized(size) => {
                         // Fixed for Windows which minimized to emit a Resized(0,0) event
-                        if size.width != 0.0 && size.height != 0.0 {
+                        glutin::WindowEvent::Resized(size)
+                            if size.width != 0.0 && size.height != 0.0 =>
+                        {
                             window.window().resize(size.to_physical(dpi_factor as f64));
                         }
-                    }
-                    &glutin::WindowEvent::KeyboardInput { input, .. } => {
-                        // issue tracked in https://github.com/tomaka/winit/issues/41
-                        // Right now we handle it manually.
-                        if cfg!(target_os = "macos") {
-                            if let Some(keycode) = input.virtual_keycode {
-                                if keycode == VirtualKeyCode::Q && input.modifiers.logo {
-                                    running = false;
+                        glutin::WindowEvent::KeyboardInput { input, .. } => {
+                            // issue tracked in https://github.com/tomaka/winit/issues/41
+                            // Right now we handle it manually.
+                            if cfg!(target_os = "macos") {
+                                if let Some(keycode) = input.virtual_keycode {
+                                    if keycode == VirtualKeyCode::Q && input.modifiers.logo {
+                                        running = false;
+                                    }
                                 }
                             }
                         }
+                        glutin::WindowEvent::DroppedFile(ref path) => {
+                            match std::fs::File::open(path) {
+                                Ok(file) => dropped_files.push_back(File(file)),
+                                Err(e) => {
+                                    App::print(format!(
+                                        "Could not open dropped file {} : {}\n",
+                                        path.display(),
+                                        e
+                                    ));
+                                    // no FileDropped event without file
+                                    return;
+                                }
+                            }
+                        }
+                        glutin::WindowEvent::Focused(b) => *focused = b,
+                        _ => (),
                     }
-                    &glutin::WindowEvent::DroppedFile(ref path) => {
-                        let filepath = path.to_str().unwrap();
-                        dropped_files.push(FileSystem::open(filepath).unwrap());
+                };
+
+                if let Some(evt) = translate_event(event, dpi_factor, pressed_keys, *focused) {
+                    let mut events = events.borrow_mut();
+                    if let Some(ref mut emulation) = *touch_emulation {
+                        emulation.translate(&evt, &mut events);
                     }
-                    _ => (),
-                },
-                _ => (),
-            };
+                    let commit = match evt {
+                        AppEvent::CharEvent(c) if text_input && !c.is_control() => {
+                            Some(AppEvent::TextCommit(c.to_string()))
+                        }
+                        _ => None,
+                    };
+                    events.push(evt);
+                    events.extend(commit);
+                }
+            });
+        }
 
-            translate_event(event, dpi_factor).map(|evt| events.borrow_mut().push(evt));
-        });
+        if let Some(ref mut replay) = self.replay {
+            let mut events = self.events.borrow_mut();
+            // the real input is ignored while replaying
+            events.clear();
+            running &= replay.next_frame(&mut events);
+        } else {
+            self.gamepads.poll(&mut self.events.borrow_mut());
+            self.clipboard.poll(&mut self.events.borrow_mut());
+        }
+        self.end_frame_events();
+
+        running
+    }
+
+    // add the injected events to the frame events, then record them and update the input state
+    fn end_frame_events(&mut self) {
+        self.events.borrow_mut().append(&mut self.injected);
+        if let Some(ref mut recorder) = self.recorder {
+            recorder.record(now(), &self.events.borrow());
+        }
+        self.input.update(&self.events.borrow());
+    }
 
-        return running;
+    /// return the keyboard and mouse state for the current frame
+    pub fn input(&self) -> &InputState {
+        &self.input
     }
 
+    /// return the state of all connected gamepads
+    pub fn gamepads<'a>(&'a self) -> Box<dyn Iterator<Item = &'a GamepadState> + 'a> {
+        self.gamepads.gamepads()
+    }
+
+    /// return the state of the gamepad with this id, if it is connected
+    pub fn gamepad(&self, id: usize) -> Option<&GamepadState> {
+        self.gamepads().find(|g| g.id == id)
+    }
+
+    /// return the content of the next dropped file, in drop order (see `AppEvent::FileDropped`)
     pub fn get_dropped_file(&mut self) -> Option<File> {
-        self.dropped_files.pop()
+        self.dropped_files.pop_front()
+    }
+
+    /// the native scancode of a W3C key code name (`KeyDownEvent.code`), as reported by winit.
+    /// None if the name is unknown or the key doesn't exist on this platform
+    pub fn scan_code(code: &str) -> Option<u32> {
+        code.parse().ok().and_then(scan_code)
     }
 
+    /// the character produced by a key with the current keyboard layout, without modifier.
+    /// For example `KeyCode::KeyW` gives "z" on an azerty keyboard.
+    /// Uses the XKB keymap on linux/X11 and a US layout elsewhere
+    pub fn key_label(&self, key_code: KeyCode) -> Option<String> {
+        let window = match self.window {
+            WindowContext::Normal(ref glwindow) => Some(glwindow.window()),
+            _ => None,
+        };
+        self.keyboard_layout.label(window, key_code)
+    }
+
+    /// the key producing a character with the current keyboard layout, see `key_label`
+    pub fn key_for_char(&self, c: char) -> Option<KeyCode> {
+        let label: String = c.to_lowercase().collect();
+        KEY_CODES
+            .iter()
+            .cloned()
+            .find(|&key_code| self.key_label(key_code).as_ref() == Some(&label))
+    }
+
+    /// add a synthetic event. It is delivered in `App.events` on next frame,
+    /// after the real input events and in injection order
+    pub fn inject_event(&mut self, event: AppEvent) {
+        self.injected.push(event);
+    }
+
+    /// run a single frame for automated tests, without the window system : the frame events are only
+    /// the ones added with `inject_event` (window, gamepad and clipboard events wait for the next
+    /// `poll_events`) and the buffers are not swapped. Returns false after `App::exit`.
+    /// Use `AppConfig.null_backend` on machines without display : `headless` still needs one
+    pub fn step<F>(&mut self, callback: F) -> bool
+    where
+        F: FnOnce(&mut Self),
+    {
+        self.end_frame_events();
+        callback(self);
+        self.events.borrow_mut().clear();
+
+        !exit_requested()
+    }
+
+    /// run a single frame : collect the window events, call the callback and swap the buffers.
+    /// Returns false when the window is closed or after `App::exit`
     pub fn poll_events<F>(&mut self, callback: F) -> bool
     where
-        F: FnOnce(&mut Self) -> (),
+        F: FnOnce(&mut Self),
     {
         if !self.handle_events() {
             return false;
@@ -317,37 +888,122 @@ impl App {
         self.events.borrow_mut().clear();
         self.window.swap_buffers().unwrap();
 
-        !self.exiting
+        !exit_requested()
     }
 
-    /// start the game loop, calling provided callback every frame
-    pub fn run<'a, F>(mut self, mut callback: F)
+    /// start the game loop, calling provided callback every frame.
+    /// Exits the current process after `App::exit`
+    pub fn run<F>(self, callback: F)
     where
-        F: FnMut(&mut Self) -> (),
+        F: FnMut(&mut Self),
     {
-        let mut running = true;
-
-        while running {
-            running = self.handle_events();
+        self.run_handler(FnHandler(callback));
+        if exit_requested() {
+            process::exit(0);
+        }
+    }
 
-            if !running {
-                break;
-            }
+    /// start the game loop, calling the handler methods every frame.
+    /// Returns when the window is closed or after `App::exit`, once the handler `exit` has been called
+    pub fn run_handler<H: AppHandler>(mut self, mut handler: H) {
+        handler.init(&mut self);
+        let mut last_time = now();
 
-            callback(&mut self);
+        while self.handle_events() {
+            let time = now();
+            handle_frame(&mut self, &mut handler, time - last_time);
+            last_time = time;
             self.events.borrow_mut().clear();
             self.window.swap_buffers().unwrap();
 
-            if self.exiting {
+            if exit_requested() {
                 break;
             }
         }
+        handler.exit(&mut self);
     }
 }
 
-/// return the time since the start of the program in seconds
+/// return the time since the start of the program in seconds.
+/// While replaying a recording, return the recorded time of the current frame
 pub fn now() -> f64 {
+    if let Some(t) = replay_time() {
+        return t;
+    }
     // precise_time_s() is in second
     // https://doc.rust-lang.org/time/time/fn.precise_time_s.html
     time::precise_time_s()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use events::{KeyDownEvent, KeyUpEvent};
+
+    fn null_app() -> App {
+        let mut config = AppConfig::new("test", (320, 200));
+        config.null_backend = true;
+        App::new(config)
+    }
+
+    fn key_down(key_code: KeyCode) -> AppEvent {
+        AppEvent::KeyDown(KeyDownEvent {
+            code: key_code.name().to_owned(),
+            key_code,
+            key: String::new(),
+            shift: false,
+            alt: false,
+            ctrl: false,
+            logo: false,
+            repeat: false,
+        })
+    }
+
+    fn key_up(key_code: KeyCode) -> AppEvent {
+        AppEvent::KeyUp(KeyUpEvent {
+            code: key_code.name().to_owned(),
+            key_code,
+            key: String::new(),
+            shift: false,
+            alt: false,
+            ctrl: false,
+            logo: false,
+            repeat: false,
+        })
+    }
+
+    #[test]
+    fn step_with_injected_events() {
+        let mut app = null_app();
+        assert_eq!(app.inner_size(), (320, 200));
+
+        app.inject_event(key_down(KeyCode::KeyW));
+        app.inject_event(AppEvent::MousePos((10.0, 20.0)));
+        assert!(app.step(|app| {
+            let events = app.events.borrow();
+            assert_eq!(events.len(), 2);
+            match events[0] {
+                AppEvent::KeyDown(ref e) => assert_eq!(e.key_code, KeyCode::KeyW),
+                _ => panic!("unexpected event {:?}", events[0]),
+            }
+            assert!(app.input().key_pressed(KeyCode::KeyW));
+            assert_eq!(app.input().mouse_pos(), (10.0, 20.0));
+        }));
+
+        // events are delivered once, the input state persists
+        assert!(app.step(|app| {
+            assert!(app.events.borrow().is_empty());
+            assert!(app.input().key_down(KeyCode::KeyW));
+            assert!(!app.input().key_pressed(KeyCode::KeyW));
+        }));
+
+        app.inject_event(key_up(KeyCode::KeyW));
+        assert!(app.step(|app| assert!(!app.input().key_down(KeyCode::KeyW))));
+    }
+
+    #[test]
+    fn step_after_exit() {
+        let mut app = null_app();
+        assert!(!app.step(|_| App::exit()));
+    }
+}
@@ -0,0 +1,434 @@
+#[cfg(target_os = "linux")]
+pub use self::x11::Clipboard;
+
+#[cfg(not(target_os = "linux"))]
+pub use self::unsupported::Clipboard;
+
+/// linux backend using the X11 CLIPBOARD selection.
+/// Native wayland is not supported : on a wayland session, this only works through XWayland,
+/// which keeps this selection in sync with the wayland clipboard. Without X server,
+/// the copied text is only available to this application.
+#[cfg(target_os = "linux")]
+mod x11 {
+    use std::ffi::CString;
+    use std::os::raw::{c_int, c_long, c_uchar, c_ulong};
+    use std::ptr;
+    use std::slice;
+    use std::sync::mpsc::{channel, Receiver, Sender};
+    use std::sync::{Arc, Mutex};
+    use std::thread::{self, JoinHandle};
+    use std::time::{Duration, Instant};
+
+    use libc;
+    use x11_dl::xlib;
+
+    use AppEvent;
+
+    /// delay before giving up when the clipboard owner doesn't answer
+    const REQUEST_TIMEOUT: Duration = Duration::from_secs(1);
+    /// how long the selection owner waits for X events before checking `set_text` and `Drop`
+    const SERVE_TIMEOUT: Duration = Duration::from_millis(100);
+
+    #[derive(Default)]
+    /// the clipboard state shared with the thread owning the selection
+    struct Owned {
+        /// text copied by the application. None when another program owns the clipboard
+        text: Option<String>,
+        /// incremented by every `set_text`
+        generation: u64,
+        /// whether a thread is serving the selection
+        serving: bool,
+        /// set when the `Clipboard` is dropped to stop the serving thread
+        stopped: bool,
+    }
+
+    /// a connection to the X server with an invisible window used to exchange the selection
+    struct Connection {
+        xlib: xlib::Xlib,
+        display: *mut xlib::Display,
+        window: xlib::Window,
+        clipboard: xlib::Atom,
+        utf8_string: xlib::Atom,
+        targets: xlib::Atom,
+        property: xlib::Atom,
+    }
+
+    impl Connection {
+        fn open() -> Option<Connection> {
+            let xlib = xlib::Xlib::open().ok()?;
+            unsafe {
+                let display = (xlib.XOpenDisplay)(ptr::null());
+                if display.is_null() {
+                    return None;
+                }
+                let root = (xlib.XDefaultRootWindow)(display);
+                let window = (xlib.XCreateSimpleWindow)(display, root, 0, 0, 1, 1, 0, 0, 0);
+                let atom = |name: &str| {
+                    let name = CString::new(name).unwrap();
+                    (xlib.XInternAtom)(display, name.as_ptr(), xlib::False)
+                };
+                let clipboard = atom("CLIPBOARD");
+                let utf8_string = atom("UTF8_STRING");
+                let targets = atom("TARGETS");
+                let property = atom("UNI_APP_CLIPBOARD");
+                Some(Connection {
+                    xlib,
+                    display,
+                    window,
+                    clipboard,
+                    utf8_string,
+                    targets,
+                    property,
+                })
+            }
+        }
+
+        /// the next X event if there is one
+        fn poll_event(&self) -> Option<xlib::XEvent> {
+            let mut event = xlib::XEvent { pad: [0; 24] };
+            unsafe {
+                if (self.xlib.XPending)(self.display) == 0 {
+                    return None;
+                }
+                (self.xlib.XNextEvent)(self.display, &mut event);
+            }
+            Some(event)
+        }
+
+        /// block until the X connection has data to read or the timeout is reached
+        fn wait(&self, timeout: Duration) {
+            let mut fd = libc::pollfd {
+                fd: unsafe { (self.xlib.XConnectionNumber)(self.display) },
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            // round up so that a sub-millisecond timeout still blocks
+            let ms = (timeout.as_micros() as u64)
+                .div_ceil(1000)
+                .min(c_int::MAX as u64);
+            unsafe {
+                libc::poll(&mut fd, 1, ms as c_int);
+            }
+        }
+
+        /// wait for the next X event. returns None if the deadline is reached
+        fn next_event(&self, deadline: Instant) -> Option<xlib::XEvent> {
+            loop {
+                if let Some(event) = self.poll_event() {
+                    return Some(event);
+                }
+                let now = Instant::now();
+                if now >= deadline {
+                    return None;
+                }
+                self.wait(deadline - now);
+            }
+        }
+
+        /// become the selection owner. returns false if the X server refused
+        fn own_selection(&self) -> bool {
+            unsafe {
+                (self.xlib.XSetSelectionOwner)(
+                    self.display,
+                    self.clipboard,
+                    self.window,
+                    xlib::CurrentTime,
+                );
+                (self.xlib.XGetSelectionOwner)(self.display, self.clipboard) == self.window
+            }
+        }
+
+        /// own the clipboard until another program takes it or the `Clipboard` is dropped.
+        /// Every `set_text` takes the ownership again, so that the latest copy wins
+        /// even when another program took the clipboard in between
+        fn serve(&self, owned: &Mutex<Owned>) {
+            let mut generation = None;
+            loop {
+                {
+                    let mut owned = owned.lock().unwrap();
+                    if owned.stopped {
+                        owned.serving = false;
+                        return;
+                    }
+                    if generation != Some(owned.generation) {
+                        generation = Some(owned.generation);
+                        if !self.own_selection() {
+                            owned.text = None;
+                            owned.serving = false;
+                            return;
+                        }
+                    }
+                }
+                let event = match self.next_event(Instant::now() + SERVE_TIMEOUT) {
+                    Some(event) => event,
+                    None => continue,
+                };
+                match event.get_type() {
+                    xlib::SelectionClear => {
+                        let mut owned = owned.lock().unwrap();
+                        // text copied since the last ownership request is served on next loop
+                        if generation == Some(owned.generation) {
+                            owned.text = None;
+                            owned.serving = false;
+                            return;
+                        }
+                    }
+                    xlib::SelectionRequest => {
+                        self.answer(unsafe { &event.selection_request }, owned)
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        /// send the clipboard content to another program
+        fn answer(&self, request: &xlib::XSelectionRequestEvent, owned: &Mutex<Owned>) {
+            // obsolete clients don't provide a property
+            let property = if request.property == 0 {
+                request.target
+            } else {
+                request.property
+            };
+            let mut reply = xlib::XSelectionEvent {
+                type_: xlib::SelectionNotify,
+                serial: 0,
+                send_event: xlib::True,
+                display: self.display,
+                requestor: request.requestor,
+                selection: request.selection,
+                target: request.target,
+                // 0 (None) means the request is refused
+                property: 0,
+                time: request.time,
+            };
+            unsafe {
+                if request.target == self.targets {
+                    let targets: [c_ulong; 3] = [self.targets, self.utf8_string, xlib::XA_STRING];
+                    (self.xlib.XChangeProperty)(
+                        self.display,
+                        request.requestor,
+                        property,
+                        xlib::XA_ATOM,
+                        32,
+                        xlib::PropModeReplace,
+                        targets.as_ptr() as *const c_uchar,
+                        targets.len() as c_int,
+                    );
+                    reply.property = property;
+                } else if request.target == self.utf8_string || request.target == xlib::XA_STRING {
+                    if let Some(ref text) = owned.lock().unwrap().text {
+                        let text = if request.target == xlib::XA_STRING {
+                            to_latin1(text)
+                        } else {
+                            text.as_bytes().to_vec()
+                        };
+                        (self.xlib.XChangeProperty)(
+                            self.display,
+                            request.requestor,
+                            property,
+                            request.target,
+                            8,
+                            xlib::PropModeReplace,
+                            text.as_ptr(),
+                            text.len() as c_int,
+                        );
+                        reply.property = property;
+                    }
+                }
+                let mut event = xlib::XEvent { selection: reply };
+                (self.xlib.XSendEvent)(self.display, request.requestor, xlib::False, 0, &mut event);
+                (self.xlib.XFlush)(self.display);
+            }
+        }
+
+        /// ask the clipboard owner for its content.
+        /// Large transfers (INCR protocol) are not supported.
+        fn read(&self) -> Option<String> {
+            unsafe {
+                (self.xlib.XConvertSelection)(
+                    self.display,
+                    self.clipboard,
+                    self.utf8_string,
+                    self.property,
+                    self.window,
+                    xlib::CurrentTime,
+                );
+                (self.xlib.XFlush)(self.display);
+            }
+            let deadline = Instant::now() + REQUEST_TIMEOUT;
+            let notify = loop {
+                let event = self.next_event(deadline)?;
+                if event.get_type() == xlib::SelectionNotify {
+                    break unsafe { event.selection };
+                }
+            };
+            if notify.property == 0 {
+                // empty clipboard or no text content
+                return None;
+            }
+            let mut actual_type = 0;
+            let mut format = 0;
+            let mut count = 0;
+            let mut bytes_after = 0;
+            let mut data = ptr::null_mut();
+            unsafe {
+                (self.xlib.XGetWindowProperty)(
+                    self.display,
+                    self.window,
+                    self.property,
+                    0,
+                    c_long::MAX / 4,
+                    xlib::True,
+                    xlib::AnyPropertyType as c_ulong,
+                    &mut actual_type,
+                    &mut format,
+                    &mut count,
+                    &mut bytes_after,
+                    &mut data,
+                );
+                if data.is_null() {
+                    return None;
+                }
+                let text = if format == 8 {
+                    Some(
+                        String::from_utf8_lossy(slice::from_raw_parts(data, count as usize))
+                            .into_owned(),
+                    )
+                } else {
+                    None
+                };
+                (self.xlib.XFree)(data as *mut _);
+                text
+            }
+        }
+    }
+
+    /// STRING selection targets are latin-1 encoded. Other characters are replaced by '?'
+    fn to_latin1(text: &str) -> Vec<u8> {
+        text.chars()
+            .map(|c| if (c as u32) < 0x100 { c as u8 } else { b'?' })
+            .collect()
+    }
+
+    impl Drop for Connection {
+        fn drop(&mut self) {
+            unsafe {
+                (self.xlib.XDestroyWindow)(self.display, self.window);
+                (self.xlib.XCloseDisplay)(self.display);
+            }
+        }
+    }
+
+    /// X11 clipboard access. Each operation uses its own connection in a background thread,
+    /// because the selection owner has to answer the other programs requests.
+    /// The copied text is no longer available to other programs once the `Clipboard` is dropped,
+    /// unless a clipboard manager saved it.
+    pub struct Clipboard {
+        owned: Arc<Mutex<Owned>>,
+        /// the thread serving the selection
+        server: Mutex<Option<JoinHandle<()>>>,
+        sender: Sender<String>,
+        receiver: Receiver<String>,
+    }
+
+    impl Clipboard {
+        pub fn new() -> Clipboard {
+            let (sender, receiver) = channel();
+            Clipboard {
+                owned: Arc::new(Mutex::new(Owned::default())),
+                server: Mutex::new(None),
+                sender,
+                receiver,
+            }
+        }
+
+        pub fn set_text(&self, text: &str) {
+            let mut owned = self.owned.lock().unwrap();
+            owned.text = Some(text.to_owned());
+            owned.generation += 1;
+            if !owned.serving {
+                owned.serving = true;
+                let shared = self.owned.clone();
+                let server = thread::spawn(move || {
+                    // without X server, the text is only available to this application
+                    if let Some(connection) = Connection::open() {
+                        connection.serve(&shared);
+                    }
+                });
+                // the previous thread stopped serving, it only has to close its connection
+                drop(owned);
+                if let Some(previous) = self.server.lock().unwrap().replace(server) {
+                    let _ = previous.join();
+                }
+            }
+        }
+
+        pub fn request_text(&self) {
+            if let Some(ref text) = self.owned.lock().unwrap().text {
+                let _ = self.sender.send(text.clone());
+                return;
+            }
+            let sender = self.sender.clone();
+            thread::spawn(move || {
+                let text = Connection::open()
+                    .and_then(|c| c.read())
+                    .unwrap_or_default();
+                let _ = sender.send(text);
+            });
+        }
+
+        /// push the received clipboard contents
+        pub fn poll(&mut self, events: &mut Vec<AppEvent>) {
+            while let Ok(text) = self.receiver.try_recv() {
+                events.push(AppEvent::ClipboardText(text));
+            }
+        }
+    }
+
+    impl Drop for Clipboard {
+        /// stop the thread serving the selection
+        fn drop(&mut self) {
+            self.owned.lock().unwrap().stopped = true;
+            if let Some(server) = self.server.lock().unwrap().take() {
+                let _ = server.join();
+            }
+        }
+    }
+}
+
+/// other operating systems only have an application local clipboard :
+/// the text is not shared with the other programs
+#[cfg(not(target_os = "linux"))]
+mod unsupported {
+    use std::cell::{Cell, RefCell};
+
+    use AppEvent;
+
+    pub struct Clipboard {
+        text: RefCell<String>,
+        requested: Cell<bool>,
+    }
+
+    impl Clipboard {
+        pub fn new() -> Clipboard {
+            Clipboard {
+                text: RefCell::new(String::new()),
+                requested: Cell::new(false),
+            }
+        }
+
+        pub fn set_text(&self, text: &str) {
+            *self.text.borrow_mut() = text.to_owned();
+        }
+
+        pub fn request_text(&self) {
+            self.requested.set(true);
+        }
+
+        pub fn poll(&mut self, events: &mut Vec<AppEvent>) {
+            if self.requested.replace(false) {
+                events.push(AppEvent::ClipboardText(self.text.borrow().clone()));
+            }
+        }
+    }
+}
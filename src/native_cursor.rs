@@ -0,0 +1,139 @@
+#[cfg(target_os = "linux")]
+pub use self::x11::CustomCursor;
+
+#[cfg(not(target_os = "linux"))]
+pub use self::unsupported::CustomCursor;
+
+/// convert straight RGBA bytes to the premultiplied ARGB pixels used by cursor apis
+#[cfg(target_os = "linux")]
+fn to_argb(rgba: &[u8]) -> Vec<u32> {
+    rgba.chunks(4)
+        .map(|p| {
+            let a = u32::from(p[3]);
+            let premultiply = |c: u8| u32::from(c) * a / 255;
+            (a << 24) | (premultiply(p[0]) << 16) | (premultiply(p[1]) << 8) | premultiply(p[2])
+        })
+        .collect()
+}
+
+/// linux backend, using an Xcursor image. Not available when the window uses wayland
+#[cfg(target_os = "linux")]
+mod x11 {
+    use glutin;
+    use glutin::os::unix::WindowExt;
+    use std::ptr;
+    use x11_dl::{xcursor, xlib};
+
+    pub struct CustomCursor {
+        libs: Option<(xlib::Xlib, xcursor::Xcursor)>,
+        display: *mut xlib::Display,
+        window: xlib::Window,
+        /// 0 when no custom cursor is used.
+        /// Cursors are freed by the X server when the window connection is closed
+        cursor: xlib::Cursor,
+    }
+
+    impl CustomCursor {
+        pub fn new() -> CustomCursor {
+            CustomCursor {
+                libs: None,
+                display: ptr::null_mut(),
+                window: 0,
+                cursor: 0,
+            }
+        }
+
+        /// create the cursor. Call `show` to actually display it
+        pub fn set(
+            &mut self,
+            window: &glutin::Window,
+            rgba: &[u8],
+            width: u32,
+            height: u32,
+            hotspot: (u32, u32),
+        ) -> Result<(), String> {
+            let (display, xwindow) = match (window.get_xlib_display(), window.get_xlib_window()) {
+                (Some(display), Some(xwindow)) => (display as *mut xlib::Display, xwindow),
+                _ => return Err("custom cursors are only supported on X11".to_owned()),
+            };
+            if self.libs.is_none() {
+                let xlib = xlib::Xlib::open().map_err(|e| e.to_string())?;
+                let xcursor = xcursor::Xcursor::open().map_err(|e| e.to_string())?;
+                self.libs = Some((xlib, xcursor));
+            }
+            let (ref xlib, ref xcursor) = *self.libs.as_ref().unwrap();
+            let pixels = super::to_argb(rgba);
+            unsafe {
+                let image = (xcursor.XcursorImageCreate)(width as i32, height as i32);
+                if image.is_null() {
+                    return Err("could not create the cursor image".to_owned());
+                }
+                (*image).xhot = hotspot.0;
+                (*image).yhot = hotspot.1;
+                ptr::copy_nonoverlapping(pixels.as_ptr(), (*image).pixels, pixels.len());
+                let cursor = (xcursor.XcursorImageLoadCursor)(display, image);
+                (xcursor.XcursorImageDestroy)(image);
+                if self.cursor != 0 {
+                    (xlib.XFreeCursor)(self.display, self.cursor);
+                }
+                self.cursor = cursor;
+            }
+            self.display = display;
+            self.window = xwindow;
+            Ok(())
+        }
+
+        /// display the custom cursor, if any
+        pub fn show(&self) {
+            if let Some((ref xlib, _)) = self.libs {
+                if self.cursor != 0 {
+                    unsafe {
+                        (xlib.XDefineCursor)(self.display, self.window, self.cursor);
+                        (xlib.XFlush)(self.display);
+                    }
+                }
+            }
+        }
+
+        /// stop using the custom cursor
+        pub fn clear(&mut self) {
+            if let Some((ref xlib, _)) = self.libs {
+                if self.cursor != 0 {
+                    unsafe {
+                        (xlib.XFreeCursor)(self.display, self.cursor);
+                    }
+                    self.cursor = 0;
+                }
+            }
+        }
+    }
+}
+
+/// other operating systems don't support custom cursors yet
+#[cfg(not(target_os = "linux"))]
+mod unsupported {
+    use glutin;
+
+    pub struct CustomCursor {}
+
+    impl CustomCursor {
+        pub fn new() -> CustomCursor {
+            CustomCursor {}
+        }
+
+        pub fn set(
+            &mut self,
+            _window: &glutin::Window,
+            _rgba: &[u8],
+            _width: u32,
+            _height: u32,
+            _hotspot: (u32, u32),
+        ) -> Result<(), String> {
+            Err("custom cursors are not supported on this platform".to_owned())
+        }
+
+        pub fn show(&self) {}
+
+        pub fn clear(&mut self) {}
+    }
+}
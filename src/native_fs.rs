@@ -1,12 +1,11 @@
 use std;
-use std::io::ErrorKind;
-use std::io::Read;
+use std::io::{Read, Write};
 use std::str;
 
 /// the root filesystem API
 pub struct FileSystem {}
 /// synchronous (native) / asynchronous (web) file API
-pub struct File(std::fs::File);
+pub struct File(pub(crate) std::fs::File);
 pub type IoError = std::io::Error;
 pub type IoErrorKind = std::io::ErrorKind;
 
@@ -17,10 +16,27 @@ impl FileSystem {
     /// You can then open this file with `FileSystem::open("config.json")`.
     /// When packaging your native project, the file should still be in static/config.json.
     /// When deploying on the web, the file should simply be in the same directory as index.html, as config.json.
+    /// On web target, a file written with [`FileSystem::write`] is read from the local storage instead,
+    /// even if the server has a file with the same path.
     pub fn open(s: &str) -> Result<File, IoError> {
         let file = std::fs::File::open(s)?;
         Ok(File(file))
     }
+    /// create or overwrite a file.
+    /// On native target, the file is written on the disk.
+    /// On web target, it is stored in the browser's local storage
+    /// and [`FileSystem::open`] will return this content instead of downloading the file.
+    pub fn write(s: &str, data: &[u8]) -> Result<(), IoError> {
+        std::fs::write(s, data)
+    }
+    /// append data at the end of a file, creating it if needed
+    pub(crate) fn append(s: &str, data: &[u8]) -> Result<(), IoError> {
+        std::fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(s)?
+            .write_all(data)
+    }
 }
 
 impl File {
@@ -33,10 +49,8 @@ impl File {
     /// Once the file has been loaded (see [`File::is_ready`]), returns the file content as a String
     pub fn read_text(&mut self) -> Result<String, IoError> {
         let mut data = String::new();
-        match self.0.read_to_string(&mut data) {
-            Ok(_) => Ok(data),
-            Err(e) => Err(std::io::Error::new(ErrorKind::Other, e)),
-        }
+        self.0.read_to_string(&mut data)?;
+        Ok(data)
     }
     /// return true if the file has been loaded
     /// On native target, files are loaded synchronously.
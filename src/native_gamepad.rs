@@ -0,0 +1,339 @@
+#[cfg(target_os = "linux")]
+pub use self::evdev::GamepadManager;
+
+#[cfg(not(target_os = "linux"))]
+pub use self::unsupported::GamepadManager;
+
+/// linux backend, reading the /dev/input/event* devices
+#[cfg(target_os = "linux")]
+mod evdev {
+    use libc;
+    use std::collections::HashSet;
+    use std::fs::{self, File, OpenOptions};
+    use std::io::{self, ErrorKind, Read};
+    use std::mem;
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::os::unix::io::AsRawFd;
+    use std::path::PathBuf;
+    use std::ptr;
+
+    use super::super::now;
+    use AppEvent;
+    use {GamepadAxis, GamepadButton, GamepadState};
+
+    // see linux/input-event-codes.h
+    const EV_KEY: u16 = 0x01;
+    const EV_ABS: u16 = 0x03;
+    const KEY_CNT: usize = 0x300;
+    const ABS_CNT: usize = 0x40;
+    const BTN_JOYSTICK: u16 = 0x120;
+    const BTN_GAMEPAD: u16 = 0x130;
+    const ABS_HAT0X: u16 = 0x10;
+    const ABS_HAT0Y: u16 = 0x11;
+
+    /// delay in seconds between two scans of /dev/input for new devices
+    const RESCAN_DELAY: f64 = 1.0;
+
+    /// _IOR('E', nr, size) ioctl request number
+    fn eviocg(nr: usize, size: usize) -> libc::c_ulong {
+        ((2 << 30) | (size << 16) | ((b'E' as usize) << 8) | nr) as libc::c_ulong
+    }
+
+    fn test_bit(bits: &[u8], bit: u16) -> bool {
+        let bit = bit as usize;
+        bits[bit / 8] & (1 << (bit % 8)) != 0
+    }
+
+    fn translate_button(code: u16) -> Option<GamepadButton> {
+        match code {
+            // generic joysticks have no standard layout, use the button order
+            0x120..=0x12f => GamepadButton::from_index((code - BTN_JOYSTICK) as usize),
+            0x130 => Some(GamepadButton::South),
+            0x131 => Some(GamepadButton::East),
+            0x133 => Some(GamepadButton::North),
+            0x134 => Some(GamepadButton::West),
+            0x136 => Some(GamepadButton::LeftShoulder),
+            0x137 => Some(GamepadButton::RightShoulder),
+            0x138 => Some(GamepadButton::LeftTrigger),
+            0x139 => Some(GamepadButton::RightTrigger),
+            0x13a => Some(GamepadButton::Select),
+            0x13b => Some(GamepadButton::Start),
+            0x13c => Some(GamepadButton::Home),
+            0x13d => Some(GamepadButton::LeftStick),
+            0x13e => Some(GamepadButton::RightStick),
+            0x220 => Some(GamepadButton::DPadUp),
+            0x221 => Some(GamepadButton::DPadDown),
+            0x222 => Some(GamepadButton::DPadLeft),
+            0x223 => Some(GamepadButton::DPadRight),
+            _ => None,
+        }
+    }
+
+    fn translate_axis(code: u16) -> Option<GamepadAxis> {
+        match code {
+            0x00 => Some(GamepadAxis::LeftStickX),
+            0x01 => Some(GamepadAxis::LeftStickY),
+            0x02 | 0x0a => Some(GamepadAxis::LeftTrigger),
+            0x03 => Some(GamepadAxis::RightStickX),
+            0x04 => Some(GamepadAxis::RightStickY),
+            0x05 | 0x09 => Some(GamepadAxis::RightTrigger),
+            _ => None,
+        }
+    }
+
+    struct Device {
+        path: PathBuf,
+        file: File,
+        state: GamepadState,
+        /// (minimum, maximum) values of each absolute axis
+        abs_range: Vec<(i32, i32)>,
+    }
+
+    impl Device {
+        /// returns Ok(None) if the device is not a gamepad
+        fn open(path: PathBuf, id: usize) -> io::Result<Option<Device>> {
+            let file = OpenOptions::new()
+                .read(true)
+                .custom_flags(libc::O_NONBLOCK)
+                .open(&path)?;
+            let fd = file.as_raw_fd();
+
+            let mut key_bits = [0u8; KEY_CNT / 8];
+            let res = unsafe {
+                libc::ioctl(
+                    fd,
+                    eviocg(0x20 + EV_KEY as usize, key_bits.len()) as _,
+                    key_bits.as_mut_ptr(),
+                )
+            };
+            if res < 0 || !(test_bit(&key_bits, BTN_GAMEPAD) || test_bit(&key_bits, BTN_JOYSTICK)) {
+                return Ok(None);
+            }
+
+            let mut name = [0u8; 256];
+            let len = unsafe { libc::ioctl(fd, eviocg(0x06, name.len()) as _, name.as_mut_ptr()) };
+            let name = if len > 0 {
+                String::from_utf8_lossy(&name[..len as usize])
+                    .trim_end_matches('\0')
+                    .to_owned()
+            } else {
+                "Unknown gamepad".to_owned()
+            };
+
+            let mut abs_bits = [0u8; ABS_CNT / 8];
+            let res = unsafe {
+                libc::ioctl(
+                    fd,
+                    eviocg(0x20 + EV_ABS as usize, abs_bits.len()) as _,
+                    abs_bits.as_mut_ptr(),
+                )
+            };
+            let mut abs_range = vec![(0, 0); ABS_CNT];
+            let mut abs_values = Vec::new();
+            if res >= 0 {
+                for code in 0..ABS_CNT as u16 {
+                    if !test_bit(&abs_bits, code) {
+                        continue;
+                    }
+                    let mut info: libc::input_absinfo = unsafe { mem::zeroed() };
+                    let res = unsafe {
+                        libc::ioctl(
+                            fd,
+                            eviocg(0x40 + code as usize, mem::size_of::<libc::input_absinfo>())
+                                as _,
+                            &mut info as *mut libc::input_absinfo,
+                        )
+                    };
+                    if res >= 0 {
+                        abs_range[code as usize] = (info.minimum, info.maximum);
+                        abs_values.push((code, info.value));
+                    }
+                }
+            }
+
+            let mut device = Device {
+                path,
+                file,
+                state: GamepadState::new(id, name),
+                abs_range,
+            };
+            // initialize the axes without generating events
+            let mut events = Vec::new();
+            for (code, value) in abs_values {
+                device.handle_event(EV_ABS, code, value, &mut events);
+            }
+            Ok(Some(device))
+        }
+
+        /// process pending input events. returns false if the device was unplugged
+        fn read_events(&mut self, events: &mut Vec<AppEvent>) -> bool {
+            let size = mem::size_of::<libc::input_event>();
+            let mut buf = vec![0u8; size * 64];
+            loop {
+                match self.file.read(&mut buf) {
+                    Ok(0) => return false,
+                    Ok(n) => {
+                        for chunk in buf[..n].chunks(size).filter(|c| c.len() == size) {
+                            let ev: libc::input_event =
+                                unsafe { ptr::read_unaligned(chunk.as_ptr() as *const _) };
+                            self.handle_event(ev.type_, ev.code, ev.value, events);
+                        }
+                    }
+                    Err(ref e) if e.kind() == ErrorKind::WouldBlock => return true,
+                    Err(ref e) if e.kind() == ErrorKind::Interrupted => (),
+                    Err(_) => return false,
+                }
+            }
+        }
+
+        fn handle_event(&mut self, type_: u16, code: u16, value: i32, events: &mut Vec<AppEvent>) {
+            match type_ {
+                EV_KEY => {
+                    if let Some(button) = translate_button(code) {
+                        self.state.set_button(button, value != 0, events);
+                    }
+                }
+                EV_ABS if code == ABS_HAT0X => {
+                    self.state
+                        .set_button(GamepadButton::DPadLeft, value < 0, events);
+                    self.state
+                        .set_button(GamepadButton::DPadRight, value > 0, events);
+                }
+                EV_ABS if code == ABS_HAT0Y => {
+                    self.state
+                        .set_button(GamepadButton::DPadUp, value < 0, events);
+                    self.state
+                        .set_button(GamepadButton::DPadDown, value > 0, events);
+                }
+                EV_ABS => {
+                    let axis = match translate_axis(code) {
+                        Some(axis) => axis,
+                        None => return,
+                    };
+                    let (min, max) = self.abs_range[code as usize];
+                    if max <= min {
+                        return;
+                    }
+                    let value = (value - min) as f32 / (max - min) as f32;
+                    match axis {
+                        GamepadAxis::LeftTrigger => {
+                            self.state.set_axis(axis, value, events);
+                            self.state
+                                .set_button(GamepadButton::LeftTrigger, value > 0.5, events);
+                        }
+                        GamepadAxis::RightTrigger => {
+                            self.state.set_axis(axis, value, events);
+                            self.state
+                                .set_button(GamepadButton::RightTrigger, value > 0.5, events);
+                        }
+                        _ => self.state.set_axis(axis, value * 2.0 - 1.0, events),
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// keeps track of the connected gamepads
+    pub struct GamepadManager {
+        devices: Vec<Option<Device>>,
+        /// devices that are not gamepads
+        ignored: HashSet<PathBuf>,
+        next_scan: f64,
+    }
+
+    impl GamepadManager {
+        pub fn new() -> GamepadManager {
+            GamepadManager {
+                devices: Vec::new(),
+                ignored: HashSet::new(),
+                next_scan: 0.0,
+            }
+        }
+
+        /// detect plugged/unplugged gamepads and push the gamepad events
+        pub fn poll(&mut self, events: &mut Vec<AppEvent>) {
+            let t = now();
+            if t >= self.next_scan {
+                self.next_scan = t + RESCAN_DELAY;
+                self.scan(events);
+            }
+            for slot in self.devices.iter_mut() {
+                let unplugged = match *slot {
+                    Some(ref mut device) => !device.read_events(events),
+                    None => false,
+                };
+                if unplugged {
+                    let id = slot.take().unwrap().state.id;
+                    events.push(AppEvent::GamepadDisconnected(id));
+                }
+            }
+        }
+
+        fn scan(&mut self, events: &mut Vec<AppEvent>) {
+            let entries = match fs::read_dir("/dev/input") {
+                Ok(entries) => entries,
+                Err(_) => return,
+            };
+            let mut present = HashSet::new();
+            for entry in entries.filter_map(Result::ok) {
+                if !entry.file_name().to_string_lossy().starts_with("event") {
+                    continue;
+                }
+                let path = entry.path();
+                present.insert(path.clone());
+                if self.ignored.contains(&path)
+                    || self.devices.iter().flatten().any(|d| d.path == path)
+                {
+                    continue;
+                }
+                let id = self
+                    .devices
+                    .iter()
+                    .position(Option::is_none)
+                    .unwrap_or(self.devices.len());
+                match Device::open(path.clone(), id) {
+                    Ok(Some(device)) => {
+                        if id == self.devices.len() {
+                            self.devices.push(Some(device));
+                        } else {
+                            self.devices[id] = Some(device);
+                        }
+                        events.push(AppEvent::GamepadConnected(id));
+                    }
+                    Ok(None) => {
+                        self.ignored.insert(path);
+                    }
+                    // probably not readable by the current user. try again on next scan
+                    Err(_) => (),
+                }
+            }
+            self.ignored.retain(|path| present.contains(path));
+        }
+
+        pub fn gamepads<'a>(&'a self) -> Box<dyn Iterator<Item = &'a GamepadState> + 'a> {
+            Box::new(self.devices.iter().flatten().map(|d| &d.state))
+        }
+    }
+}
+
+/// other operating systems have no gamepad support yet
+#[cfg(not(target_os = "linux"))]
+mod unsupported {
+    use AppEvent;
+    use GamepadState;
+
+    pub struct GamepadManager {}
+
+    impl GamepadManager {
+        pub fn new() -> GamepadManager {
+            GamepadManager {}
+        }
+
+        pub fn poll(&mut self, _events: &mut Vec<AppEvent>) {}
+
+        pub fn gamepads<'a>(&'a self) -> Box<dyn Iterator<Item = &'a GamepadState> + 'a> {
+            Box::new(None.into_iter())
+        }
+    }
+}
@@ -1,633 +1,725 @@
 use glutin::VirtualKeyCode;
 use glutin::VirtualKeyCode::*;
 
-pub fn translate_virtual_key(c: VirtualKeyCode) -> &'static str {
+use KeyCode;
+
+pub fn translate_virtual_key(c: VirtualKeyCode) -> KeyCode {
     match c {
-        Key1 => "Digit1",
-        Key2 => "Digit2",
-        Key3 => "Digit3",
-        Key4 => "Digit4",
-        Key5 => "Digit5",
-        Key6 => "Digit6",
-        Key7 => "Digit7",
-        Key8 => "Digit8",
-        Key9 => "Digit9",
-        Key0 => "Digit0",
-        A => "KeyA",
-        B => "KeyB",
-        C => "KeyC",
-        D => "KeyD",
-        E => "KeyE",
-        F => "KeyF",
-        G => "KeyG",
-        H => "KeyH",
-        I => "KeyI",
-        J => "KeyJ",
-        K => "KeyK",
-        L => "KeyL",
-        M => "KeyM",
-        N => "KeyN",
-        O => "KeyO",
-        P => "KeyP",
-        Q => "KeyQ",
-        R => "KeyR",
-        S => "KeyS",
-        T => "KeyT",
-        U => "KeyU",
-        V => "KeyV",
-        W => "KeyW",
-        X => "KeyX",
-        Y => "KeyY",
-        Z => "KeyZ",
-        Escape => "Escape",
-        F1 => "F1",
-        F2 => "F2",
-        F3 => "F3",
-        F4 => "F4",
-        F5 => "F5",
-        F6 => "F6",
-        F7 => "F7",
-        F8 => "F8",
-        F9 => "F9",
-        F10 => "F10",
-        F11 => "F11",
-        F12 => "F12",
-        F13 => "F13",
-        F14 => "F14",
-        F15 => "F15",
-        Snapshot => "",
-        Scroll => "ScrollLock",
-        Pause => "Pause",
-        Insert => "Insert",
-        Home => "Home",
-        Delete => "Delete",
-        End => "End",
-        PageDown => "PageDown",
-        PageUp => "PageUp",
-        Left => "ArrowLeft",
-        Up => "ArrowUp",
-        Right => "ArrowRight",
-        Down => "ArrowDown",
-        Back => "Backspace",
-        Return => "Enter",
-        Space => "Space",
-        Compose => "",
-        Numlock => "NumLock",
-        Numpad0 => "Numpad0",
-        Numpad1 => "Numpad1",
-        Numpad2 => "Numpad2",
-        Numpad3 => "Numpad3",
-        Numpad4 => "Numpad4",
-        Numpad5 => "Numpad5",
-        Numpad6 => "Numpad6",
-        Numpad7 => "Numpad7",
-        Numpad8 => "Numpad8",
-        Numpad9 => "Numpad9",
-        AbntC1 => "",
-        AbntC2 => "",
-        Add => "NumpadAdd",
-        Apostrophe => "Backslash",
-        Apps => "",
-        At => "",
-        Ax => "",
-        Backslash => "Backslash",
-        Calculator => "",
-        Capital => "CapsLock",
-        Colon => "",
-        Comma => "Comma",
-        Convert => "",
-        Decimal => "NumpadDecimal",
-        Divide => "NumpadDivide",
-        Equals => "Equal",
-        Grave => "Backquote",
-        Kana => "",
-        Kanji => "",
-        LAlt => "AltLeft",
-        LBracket => "BracketLeft",
-        LControl => "ControlLeft",
-        LShift => "ShiftLeft",
-        LWin => "",
-        Mail => "",
-        MediaSelect => "",
-        MediaStop => "",
-        Minus => "Minus",
-        Multiply => "NumpadMultiply",
-        Mute => "",
-        MyComputer => "",
-        NavigateForward => "",
-        NavigateBackward => "",
-        NextTrack => "",
-        NoConvert => "",
-        NumpadComma => "NumpadComma",
-        NumpadEnter => "NumpadEnter",
-        NumpadEquals => "NumpadEqual",
-        OEM102 => "",
-        Period => "Period",
-        PlayPause => "",
-        Power => "",
-        PrevTrack => "",
-        RAlt => "AltRight",
-        RBracket => "BracketRight",
-        RControl => "ControlRight",
-        RShift => "ShiftRight",
-        RWin => "",
-        Semicolon => "Semicolon",
-        Slash => "Slash",
-        Sleep => "",
-        Stop => "",
-        Subtract => "NumpadSubtract",
-        Sysrq => "",
-        Tab => "Tab",
-        Underline => "",
-        Unlabeled => "",
-        VolumeDown => "",
-        VolumeUp => "",
-        Wake => "",
-        WebBack => "",
-        WebFavorites => "",
-        WebForward => "",
-        WebHome => "",
-        WebRefresh => "",
-        WebSearch => "",
-        WebStop => "",
-        Yen => "",
-        Caret => "Caret",
-        Copy => "Copy",
-        Paste => "Paste",
-        Cut => "Cut",
+        Key1 => KeyCode::Digit1,
+        Key2 => KeyCode::Digit2,
+        Key3 => KeyCode::Digit3,
+        Key4 => KeyCode::Digit4,
+        Key5 => KeyCode::Digit5,
+        Key6 => KeyCode::Digit6,
+        Key7 => KeyCode::Digit7,
+        Key8 => KeyCode::Digit8,
+        Key9 => KeyCode::Digit9,
+        Key0 => KeyCode::Digit0,
+        A => KeyCode::KeyA,
+        B => KeyCode::KeyB,
+        C => KeyCode::KeyC,
+        D => KeyCode::KeyD,
+        E => KeyCode::KeyE,
+        F => KeyCode::KeyF,
+        G => KeyCode::KeyG,
+        H => KeyCode::KeyH,
+        I => KeyCode::KeyI,
+        J => KeyCode::KeyJ,
+        K => KeyCode::KeyK,
+        L => KeyCode::KeyL,
+        M => KeyCode::KeyM,
+        N => KeyCode::KeyN,
+        O => KeyCode::KeyO,
+        P => KeyCode::KeyP,
+        Q => KeyCode::KeyQ,
+        R => KeyCode::KeyR,
+        S => KeyCode::KeyS,
+        T => KeyCode::KeyT,
+        U => KeyCode::KeyU,
+        V => KeyCode::KeyV,
+        W => KeyCode::KeyW,
+        X => KeyCode::KeyX,
+        Y => KeyCode::KeyY,
+        Z => KeyCode::KeyZ,
+        Escape => KeyCode::Escape,
+        F1 => KeyCode::F1,
+        F2 => KeyCode::F2,
+        F3 => KeyCode::F3,
+        F4 => KeyCode::F4,
+        F5 => KeyCode::F5,
+        F6 => KeyCode::F6,
+        F7 => KeyCode::F7,
+        F8 => KeyCode::F8,
+        F9 => KeyCode::F9,
+        F10 => KeyCode::F10,
+        F11 => KeyCode::F11,
+        F12 => KeyCode::F12,
+        F13 => KeyCode::F13,
+        F14 => KeyCode::F14,
+        F15 => KeyCode::F15,
+        Snapshot => KeyCode::PrintScreen,
+        Scroll => KeyCode::ScrollLock,
+        Pause => KeyCode::Pause,
+        Insert => KeyCode::Insert,
+        Home => KeyCode::Home,
+        Delete => KeyCode::Delete,
+        End => KeyCode::End,
+        PageDown => KeyCode::PageDown,
+        PageUp => KeyCode::PageUp,
+        Left => KeyCode::ArrowLeft,
+        Up => KeyCode::ArrowUp,
+        Right => KeyCode::ArrowRight,
+        Down => KeyCode::ArrowDown,
+        Back => KeyCode::Backspace,
+        Return => KeyCode::Enter,
+        Space => KeyCode::Space,
+        Compose => KeyCode::Unidentified(c as u32),
+        Numlock => KeyCode::NumLock,
+        Numpad0 => KeyCode::Numpad0,
+        Numpad1 => KeyCode::Numpad1,
+        Numpad2 => KeyCode::Numpad2,
+        Numpad3 => KeyCode::Numpad3,
+        Numpad4 => KeyCode::Numpad4,
+        Numpad5 => KeyCode::Numpad5,
+        Numpad6 => KeyCode::Numpad6,
+        Numpad7 => KeyCode::Numpad7,
+        Numpad8 => KeyCode::Numpad8,
+        Numpad9 => KeyCode::Numpad9,
+        AbntC1 => KeyCode::IntlRo,
+        AbntC2 => KeyCode::Unidentified(c as u32),
+        Add => KeyCode::NumpadAdd,
+        Apostrophe => KeyCode::Quote,
+        Apps => KeyCode::ContextMenu,
+        At => KeyCode::Unidentified(c as u32),
+        Ax => KeyCode::Unidentified(c as u32),
+        Backslash => KeyCode::Backslash,
+        Calculator => KeyCode::LaunchApp2,
+        Capital => KeyCode::CapsLock,
+        Colon => KeyCode::Unidentified(c as u32),
+        Comma => KeyCode::Comma,
+        Convert => KeyCode::Convert,
+        Decimal => KeyCode::NumpadDecimal,
+        Divide => KeyCode::NumpadDivide,
+        Equals => KeyCode::Equal,
+        Grave => KeyCode::Backquote,
+        Kana => KeyCode::KanaMode,
+        Kanji => KeyCode::Unidentified(c as u32),
+        LAlt => KeyCode::AltLeft,
+        LBracket => KeyCode::BracketLeft,
+        LControl => KeyCode::ControlLeft,
+        LShift => KeyCode::ShiftLeft,
+        LWin => KeyCode::MetaLeft,
+        Mail => KeyCode::LaunchMail,
+        MediaSelect => KeyCode::MediaSelect,
+        MediaStop => KeyCode::MediaStop,
+        Minus => KeyCode::Minus,
+        Multiply => KeyCode::NumpadMultiply,
+        Mute => KeyCode::AudioVolumeMute,
+        MyComputer => KeyCode::LaunchApp1,
+        NavigateForward => KeyCode::BrowserForward,
+        NavigateBackward => KeyCode::BrowserBack,
+        NextTrack => KeyCode::MediaTrackNext,
+        NoConvert => KeyCode::NonConvert,
+        NumpadComma => KeyCode::NumpadComma,
+        NumpadEnter => KeyCode::NumpadEnter,
+        NumpadEquals => KeyCode::NumpadEqual,
+        OEM102 => KeyCode::IntlBackslash,
+        Period => KeyCode::Period,
+        PlayPause => KeyCode::MediaPlayPause,
+        Power => KeyCode::Power,
+        PrevTrack => KeyCode::MediaTrackPrevious,
+        RAlt => KeyCode::AltRight,
+        RBracket => KeyCode::BracketRight,
+        RControl => KeyCode::ControlRight,
+        RShift => KeyCode::ShiftRight,
+        RWin => KeyCode::MetaRight,
+        Semicolon => KeyCode::Semicolon,
+        Slash => KeyCode::Slash,
+        Sleep => KeyCode::Sleep,
+        Stop => KeyCode::Unidentified(c as u32),
+        Subtract => KeyCode::NumpadSubtract,
+        Sysrq => KeyCode::Unidentified(c as u32),
+        Tab => KeyCode::Tab,
+        Underline => KeyCode::Unidentified(c as u32),
+        Unlabeled => KeyCode::Unidentified(c as u32),
+        VolumeDown => KeyCode::AudioVolumeDown,
+        VolumeUp => KeyCode::AudioVolumeUp,
+        Wake => KeyCode::WakeUp,
+        WebBack => KeyCode::BrowserBack,
+        WebFavorites => KeyCode::BrowserFavorites,
+        WebForward => KeyCode::BrowserForward,
+        WebHome => KeyCode::BrowserHome,
+        WebRefresh => KeyCode::BrowserRefresh,
+        WebSearch => KeyCode::BrowserSearch,
+        WebStop => KeyCode::BrowserStop,
+        Yen => KeyCode::IntlYen,
+        Caret => KeyCode::Unidentified(c as u32),
+        Copy => KeyCode::Copy,
+        Paste => KeyCode::Paste,
+        Cut => KeyCode::Cut,
     }
 }
+
+/// platform whose scancodes are reported by winit.
+/// The tables of the other platforms are only used by the tests
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Platform {
+    MacOs,
+    Windows,
+    Linux,
+}
+
 #[cfg(target_os = "macos")]
-pub fn translate_scan_code(c: u32) -> &'static str {
-    match c {
-        0x12 => "Digit1",
-        0x13 => "Digit2",
-        0x14 => "Digit3",
-        0x15 => "Digit4",
-        0x17 => "Digit5",
-        0x16 => "Digit6",
-        0x1A => "Digit7",
-        0x1C => "Digit8",
-        0x19 => "Digit9",
-        0x1D => "Digit0",
-        0x00 => "KeyA",
-        0x0B => "KeyB",
-        0x08 => "KeyC",
-        0x02 => "KeyD",
-        0x0E => "KeyE",
-        0x03 => "KeyF",
-        0x05 => "KeyG",
-        0x04 => "KeyH",
-        0x22 => "KeyI",
-        0x26 => "KeyJ",
-        0x28 => "KeyK",
-        0x25 => "KeyL",
-        0x2E => "KeyM",
-        0x2D => "KeyN",
-        0x1F => "KeyO",
-        0x23 => "KeyP",
-        0x0C => "KeyQ",
-        0x0F => "KeyR",
-        0x01 => "KeyS",
-        0x11 => "KeyT",
-        0x20 => "KeyU",
-        0x09 => "KeyV",
-        0x0D => "KeyW",
-        0x07 => "KeyX",
-        0x10 => "KeyY",
-        0x06 => "KeyZ",
-        0x35 => "Escape",
-        0x7A => "F1",
-        0x78 => "F2",
-        0x63 => "F3",
-        0x76 => "F4",
-        0x60 => "F5",
-        0x61 => "F6",
-        0x62 => "F7",
-        0x64 => "F8",
-        0x65 => "F9",
-        0x6D => "F10",
-        0x67 => "F11",
-        0x6F => "F12",
-        0x69 => "F13",
-        0x6B => "F14",
-        0x71 => "F15",
-        // 0x37 => "Snapshot",
-        // 0x46 => "ScrollLock",
-        // Pause => "Pause",
-        // 0x52 => "Insert",
-        0x73 => "Home",
-        0x75 => "Delete",
-        0x77 => "End",
-        0x79 => "PageDown",
-        0x74 => "PageUp",
-        0x7B => "ArrowLeft",
-        0x7E => "ArrowUp",
-        0x7C => "ArrowRight",
-        0x7D => "ArrowDown",
-        0x33 => "Backspace",
-        0x24 => "Enter",
-        0x31 => "Space",
-        // Compose => "",
-        0x47 => "NumLock",
-        0x52 => "Numpad0",
-        0x53 => "Numpad1",
-        0x54 => "Numpad2",
-        0x55 => "Numpad3",
-        0x56 => "Numpad4",
-        0x57 => "Numpad5",
-        0x58 => "Numpad6",
-        0x59 => "Numpad7",
-        0x5B => "Numpad8",
-        0x5C => "Numpad9",
-        // AbntC1 => "",
-        // AbntC2 => "",
-        0x45 => "NumpadAdd",
-        // Apostrophe => "Backslash",
-        // Apps => "",
-        // At => "",
-        // Ax => "",
-        0x2A => "Backslash",
-        // Calculator => "",
-        0x39 => "CapsLock",
-        0x29 => "Colon",
-        0x2B => "Comma",
-        // Convert => "",
-        0x41 => "NumpadDecimal",
-        0x4B => "NumpadDivide",
-        0x18 => "Equal",
-        0x32 => "Backquote",
-        // Kana => "",
-        // Kanji => "",
-        // LAlt => "",
-        0x21 => "BracketLeft",
-        0x3B => "ControlLeft",
-        // 0x38 => "AltLeft",
-        0x38 => "ShiftLeft",
-        0x37 => "Command",
-        // 0x5B => "LeftWin",
-        // Mail => "",
-        // MediaSelect => "",
-        // MediaStop => "",
-        0x1B => "Minus",
-        0x43 => "NumpadMultiply",
-        // Mute => "",
-        // MyComputer => "",
-        // NavigateForward => "",
-        // NavigateBackward => "",
-        // NextTrack => "",
-        // NoConvert => "",
-        // NumpadComma => "NumpadComma",
-        0x4C => "NumpadEnter",
-        0x51 => "NumpadEqual",
-        // OEM102 => "",
-        0x2F => "Period",
-        // PlayPause => "",
-        // Power => "",
-        // PrevTrack => "",
-        // RAlt => "",
-        0x1E => "BracketRight",
-        // RControl => "ControlRight", // Same as ControlLeft
-        // RMenu => "AltRight", // Same as ControlLeft
-        // 0x36 => "ShiftRight",
-        // RWin => "",
-        // 0x27 => "Semicolon",
-        0x2C => "Slash",
-        // Sleep => "",
-        // Stop => "",
-        0x4E => "NumpadSubtract",
-        // Sysrq => "",
-        0x30 => "Tab",
-        // Underline => "",
-        // Unlabeled => "",
-        // VolumeDown => "",
-        // VolumeUp => "",
-        // Wake => "",
-        // WebBack => "",
-        // WebFavorites => "",
-        // WebForward => "",
-        // WebHome => "",
-        // WebRefresh => "",
-        // WebSearch => "",
-        // WebStop => "",
-        // Yen => "",
-        // Caret => "Caret",
-        _ => "",
+const PLATFORM: Platform = Platform::MacOs;
+#[cfg(target_os = "windows")]
+const PLATFORM: Platform = Platform::Windows;
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const PLATFORM: Platform = Platform::Linux;
+
+/// the platform has no such key
+const NONE: u32 = u32::MAX;
+
+/// scancodes of each key code : (key code, macos, windows, linux)
+/// * macos : virtual key codes (`kVK_*` constants)
+/// * windows : set 1 scancodes, with the 0xE0 prefix for extended keys
+/// * linux : evdev codes (X11 keycode - 8)
+#[rustfmt::skip]
+const SCAN_CODES: &[(KeyCode, u32, u32, u32)] = &[
+    (KeyCode::Backquote, 0x32, 0x29, 41),
+    (KeyCode::Backslash, 0x2A, 0x2B, 43),
+    (KeyCode::BracketLeft, 0x21, 0x1A, 26),
+    (KeyCode::BracketRight, 0x1E, 0x1B, 27),
+    (KeyCode::Comma, 0x2B, 0x33, 51),
+    (KeyCode::Digit0, 0x1D, 0x0B, 11),
+    (KeyCode::Digit1, 0x12, 0x02, 2),
+    (KeyCode::Digit2, 0x13, 0x03, 3),
+    (KeyCode::Digit3, 0x14, 0x04, 4),
+    (KeyCode::Digit4, 0x15, 0x05, 5),
+    (KeyCode::Digit5, 0x17, 0x06, 6),
+    (KeyCode::Digit6, 0x16, 0x07, 7),
+    (KeyCode::Digit7, 0x1A, 0x08, 8),
+    (KeyCode::Digit8, 0x1C, 0x09, 9),
+    (KeyCode::Digit9, 0x19, 0x0A, 10),
+    (KeyCode::Equal, 0x18, 0x0D, 13),
+    (KeyCode::IntlBackslash, 0x0A, 0x56, 86),
+    (KeyCode::IntlRo, 0x5E, 0x73, 89),
+    (KeyCode::IntlYen, 0x5D, 0x7D, 124),
+    (KeyCode::KeyA, 0x00, 0x1E, 30),
+    (KeyCode::KeyB, 0x0B, 0x30, 48),
+    (KeyCode::KeyC, 0x08, 0x2E, 46),
+    (KeyCode::KeyD, 0x02, 0x20, 32),
+    (KeyCode::KeyE, 0x0E, 0x12, 18),
+    (KeyCode::KeyF, 0x03, 0x21, 33),
+    (KeyCode::KeyG, 0x05, 0x22, 34),
+    (KeyCode::KeyH, 0x04, 0x23, 35),
+    (KeyCode::KeyI, 0x22, 0x17, 23),
+    (KeyCode::KeyJ, 0x26, 0x24, 36),
+    (KeyCode::KeyK, 0x28, 0x25, 37),
+    (KeyCode::KeyL, 0x25, 0x26, 38),
+    (KeyCode::KeyM, 0x2E, 0x32, 50),
+    (KeyCode::KeyN, 0x2D, 0x31, 49),
+    (KeyCode::KeyO, 0x1F, 0x18, 24),
+    (KeyCode::KeyP, 0x23, 0x19, 25),
+    (KeyCode::KeyQ, 0x0C, 0x10, 16),
+    (KeyCode::KeyR, 0x0F, 0x13, 19),
+    (KeyCode::KeyS, 0x01, 0x1F, 31),
+    (KeyCode::KeyT, 0x11, 0x14, 20),
+    (KeyCode::KeyU, 0x20, 0x16, 22),
+    (KeyCode::KeyV, 0x09, 0x2F, 47),
+    (KeyCode::KeyW, 0x0D, 0x11, 17),
+    (KeyCode::KeyX, 0x07, 0x2D, 45),
+    (KeyCode::KeyY, 0x10, 0x15, 21),
+    (KeyCode::KeyZ, 0x06, 0x2C, 44),
+    (KeyCode::Minus, 0x1B, 0x0C, 12),
+    (KeyCode::Period, 0x2F, 0x34, 52),
+    (KeyCode::Quote, 0x27, 0x28, 40),
+    (KeyCode::Semicolon, 0x29, 0x27, 39),
+    (KeyCode::Slash, 0x2C, 0x35, 53),
+    (KeyCode::AltLeft, 0x3A, 0x38, 56),
+    (KeyCode::AltRight, 0x3D, 0xE038, 100),
+    (KeyCode::Backspace, 0x33, 0x0E, 14),
+    (KeyCode::CapsLock, 0x39, 0x3A, 58),
+    (KeyCode::ContextMenu, 0x6E, 0xE05D, 127),
+    (KeyCode::ControlLeft, 0x3B, 0x1D, 29),
+    (KeyCode::ControlRight, 0x3E, 0xE01D, 97),
+    (KeyCode::Enter, 0x24, 0x1C, 28),
+    (KeyCode::MetaLeft, 0x37, 0xE05B, 125),
+    (KeyCode::MetaRight, 0x36, 0xE05C, 126),
+    (KeyCode::ShiftLeft, 0x38, 0x2A, 42),
+    (KeyCode::ShiftRight, 0x3C, 0x36, 54),
+    (KeyCode::Space, 0x31, 0x39, 57),
+    (KeyCode::Tab, 0x30, 0x0F, 15),
+    (KeyCode::Convert, NONE, 0x79, 92),
+    (KeyCode::KanaMode, NONE, 0x70, 93),
+    (KeyCode::Lang1, 0x68, 0x72, 122),
+    (KeyCode::Lang2, 0x66, 0x71, 123),
+    (KeyCode::NonConvert, NONE, 0x7B, 94),
+    (KeyCode::Delete, 0x75, 0xE053, 111),
+    (KeyCode::End, 0x77, 0xE04F, 107),
+    (KeyCode::Help, NONE, NONE, 138),
+    (KeyCode::Home, 0x73, 0xE047, 102),
+    // the mac help key is at the insert key position
+    (KeyCode::Insert, 0x72, 0xE052, 110),
+    (KeyCode::PageDown, 0x79, 0xE051, 109),
+    (KeyCode::PageUp, 0x74, 0xE049, 104),
+    (KeyCode::ArrowDown, 0x7D, 0xE050, 108),
+    (KeyCode::ArrowLeft, 0x7B, 0xE04B, 105),
+    (KeyCode::ArrowRight, 0x7C, 0xE04D, 106),
+    (KeyCode::ArrowUp, 0x7E, 0xE048, 103),
+    // the mac clear key is at the num lock key position
+    (KeyCode::NumLock, 0x47, 0xE045, 69),
+    (KeyCode::Numpad0, 0x52, 0x52, 82),
+    (KeyCode::Numpad1, 0x53, 0x4F, 79),
+    (KeyCode::Numpad2, 0x54, 0x50, 80),
+    (KeyCode::Numpad3, 0x55, 0x51, 81),
+    (KeyCode::Numpad4, 0x56, 0x4B, 75),
+    (KeyCode::Numpad5, 0x57, 0x4C, 76),
+    (KeyCode::Numpad6, 0x58, 0x4D, 77),
+    (KeyCode::Numpad7, 0x59, 0x47, 71),
+    (KeyCode::Numpad8, 0x5B, 0x48, 72),
+    (KeyCode::Numpad9, 0x5C, 0x49, 73),
+    (KeyCode::NumpadAdd, 0x45, 0x4E, 78),
+    (KeyCode::NumpadComma, 0x5F, 0x7E, 121),
+    (KeyCode::NumpadDecimal, 0x41, 0x53, 83),
+    (KeyCode::NumpadDivide, 0x4B, 0xE035, 98),
+    (KeyCode::NumpadEnter, 0x4C, 0xE01C, 96),
+    (KeyCode::NumpadEqual, 0x51, 0x59, 117),
+    (KeyCode::NumpadMultiply, 0x43, 0x37, 55),
+    (KeyCode::NumpadParenLeft, NONE, NONE, 179),
+    (KeyCode::NumpadParenRight, NONE, NONE, 180),
+    (KeyCode::NumpadSubtract, 0x4E, 0x4A, 74),
+    (KeyCode::Escape, 0x35, 0x01, 1),
+    (KeyCode::F1, 0x7A, 0x3B, 59),
+    (KeyCode::F2, 0x78, 0x3C, 60),
+    (KeyCode::F3, 0x63, 0x3D, 61),
+    (KeyCode::F4, 0x76, 0x3E, 62),
+    (KeyCode::F5, 0x60, 0x3F, 63),
+    (KeyCode::F6, 0x61, 0x40, 64),
+    (KeyCode::F7, 0x62, 0x41, 65),
+    (KeyCode::F8, 0x64, 0x42, 66),
+    (KeyCode::F9, 0x65, 0x43, 67),
+    (KeyCode::F10, 0x6D, 0x44, 68),
+    (KeyCode::F11, 0x67, 0x57, 87),
+    (KeyCode::F12, 0x6F, 0x58, 88),
+    (KeyCode::F13, 0x69, 0x64, 183),
+    (KeyCode::F14, 0x6B, 0x65, 184),
+    (KeyCode::F15, 0x71, 0x66, 185),
+    (KeyCode::F16, 0x6A, 0x67, 186),
+    (KeyCode::F17, 0x40, 0x68, 187),
+    (KeyCode::F18, 0x4F, 0x69, 188),
+    (KeyCode::F19, 0x50, 0x6A, 189),
+    (KeyCode::F20, 0x5A, 0x6B, 190),
+    (KeyCode::F21, NONE, 0x6C, 191),
+    (KeyCode::F22, NONE, 0x6D, 192),
+    (KeyCode::F23, NONE, 0x6E, 193),
+    (KeyCode::F24, NONE, 0x76, 194),
+    (KeyCode::Fn, 0x3F, NONE, NONE),
+    (KeyCode::PrintScreen, NONE, 0xE037, 99),
+    (KeyCode::ScrollLock, NONE, 0x46, 70),
+    (KeyCode::Pause, NONE, 0x45, 119),
+    (KeyCode::BrowserBack, NONE, 0xE06A, 158),
+    (KeyCode::BrowserFavorites, NONE, 0xE066, 156),
+    (KeyCode::BrowserForward, NONE, 0xE069, 159),
+    (KeyCode::BrowserHome, NONE, 0xE032, 172),
+    (KeyCode::BrowserRefresh, NONE, 0xE067, 173),
+    (KeyCode::BrowserSearch, NONE, 0xE065, 217),
+    (KeyCode::BrowserStop, NONE, 0xE068, 128),
+    (KeyCode::Eject, NONE, NONE, 161),
+    (KeyCode::LaunchApp1, NONE, 0xE06B, 157),
+    (KeyCode::LaunchApp2, NONE, 0xE021, 140),
+    (KeyCode::LaunchMail, NONE, 0xE06C, 155),
+    (KeyCode::MediaPlayPause, NONE, 0xE022, 164),
+    (KeyCode::MediaSelect, NONE, 0xE06D, 226),
+    (KeyCode::MediaStop, NONE, 0xE024, 166),
+    (KeyCode::MediaTrackNext, NONE, 0xE019, 163),
+    (KeyCode::MediaTrackPrevious, NONE, 0xE010, 165),
+    (KeyCode::Power, NONE, 0xE05E, 116),
+    (KeyCode::Sleep, NONE, 0xE05F, 142),
+    (KeyCode::AudioVolumeDown, 0x49, 0xE02E, 114),
+    (KeyCode::AudioVolumeMute, 0x4A, 0xE020, 113),
+    (KeyCode::AudioVolumeUp, 0x48, 0xE030, 115),
+    (KeyCode::WakeUp, NONE, 0xE063, 143),
+    (KeyCode::Again, NONE, NONE, 129),
+    (KeyCode::Copy, NONE, NONE, 133),
+    (KeyCode::Cut, NONE, NONE, 137),
+    (KeyCode::Find, NONE, NONE, 136),
+    (KeyCode::Open, NONE, NONE, 134),
+    (KeyCode::Paste, NONE, NONE, 135),
+    (KeyCode::Props, NONE, NONE, 130),
+    (KeyCode::Select, NONE, NONE, NONE),
+    (KeyCode::Undo, NONE, NONE, 131),
+];
+
+fn platform_scan_code(platform: Platform, entry: &(KeyCode, u32, u32, u32)) -> u32 {
+    match platform {
+        Platform::MacOs => entry.1,
+        Platform::Windows => entry.2,
+        Platform::Linux => entry.3,
     }
 }
 
-#[cfg(target_os = "windows")]
-pub fn translate_scan_code(c: u32) -> &'static str {
-    match c {
-        0x02 => "Digit1",
-        0x03 => "Digit2",
-        0x04 => "Digit3",
-        0x05 => "Digit4",
-        0x06 => "Digit5",
-        0x07 => "Digit6",
-        0x08 => "Digit7",
-        0x09 => "Digit8",
-        0x0A => "Digit9",
-        0x0B => "Digit0",
-        0x1E => "KeyA",
-        0x30 => "KeyB",
-        0x2E => "KeyC",
-        0x20 => "KeyD",
-        0x12 => "KeyE",
-        0x21 => "KeyF",
-        0x22 => "KeyG",
-        0x23 => "KeyH",
-        0x17 => "KeyI",
-        0x24 => "KeyJ",
-        0x25 => "KeyK",
-        0x26 => "KeyL",
-        0x32 => "KeyM",
-        0x31 => "KeyN",
-        0x18 => "KeyO",
-        0x19 => "KeyP",
-        0x10 => "KeyQ",
-        0x13 => "KeyR",
-        0x1F => "KeyS",
-        0x14 => "KeyT",
-        0x16 => "KeyU",
-        0x2F => "KeyV",
-        0x11 => "KeyW",
-        0x2D => "KeyX",
-        0x15 => "KeyY",
-        0x2C => "KeyZ",
-        0x01 => "Escape",
-        0x3B => "F1",
-        0x3C => "F2",
-        0x3D => "F3",
-        0x3E => "F4",
-        0x3F => "F5",
-        0x40 => "F6",
-        0x41 => "F7",
-        0x42 => "F8",
-        0x43 => "F9",
-        0x44 => "F10",
-        0x85 => "F11",
-        0x86 => "F12",
-        // F13 => "F13",
-        // F14 => "F14",
-        // F15 => "F15",
-        0x37 => "Snapshot",
-        0x46 => "ScrollLock",
-        // Pause => "Pause",
-        0x52 => "Insert",
-        0x47 => "Home",
-        0x53 => "Delete",
-        0x4F => "End",
-        0x51 => "PageDown",
-        0x49 => "PageUp",
-        0x4B => "ArrowLeft",
-        0x48 => "ArrowUp",
-        0x4D => "ArrowRight",
-        0x50 => "ArrowDown",
-        0x0E => "Backspace",
-        0x1C => "Enter",
-        0x39 => "Space",
-        // Compose => "",
-        0x45 => "NumLock",
-        // Numpad0 => "Numpad0", // Same as Insert
-        // Numpad1 => "Numpad1", // Same as End
-        // Numpad2 => "Numpad2", // Same as ArrowDown
-        // Numpad3 => "Numpad3", // Same as PageDown
-        // Numpad4 => "Numpad4", // Same as ArrowLeft
-        0x4C => "Numpad5",
-        // Numpad6 => "Numpad6", // Same as ArrowRight
-        // Numpad7 => "Numpad7", // Same as Home
-        // Numpad8 => "Numpad8", // Same as ArrowUp
-        // Numpad9 => "Numpad9", // Same as PageUp
-        // AbntC1 => "",
-        // AbntC2 => "",
-        0x4E => "NumpadAdd",
-        // Apostrophe => "Backslash",
-        // Apps => "",
-        // At => "",
-        // Ax => "",
-        0x2B => "Backslash",
-        // Calculator => "",
-        0x3A => "CapsLock",
-        0x27 => "Colon",
-        0x33 => "Comma",
-        // Convert => "",
-        // Decimal => "NumpadDecimal", // Same as Delete
-        0x35 => "NumpadDivide",
-        0x0D => "Equal",
-        0x29 => "Backquote",
-        // Kana => "",
-        // Kanji => "",
-        // LAlt => "",
-        0x1A => "BracketLeft",
-        0x1D => "ControlLeft",
-        0x38 => "AltLeft",
-        0x2A => "ShiftLeft",
-        0x5B => "LeftWin",
-        // Mail => "",
-        // MediaSelect => "",
-        // MediaStop => "",
-        0x0C => "Minus",
-        // Multiply => "NumpadMultiply", // Same as Snapshot
-        // Mute => "",
-        // MyComputer => "",
-        // NavigateForward => "",
-        // NavigateBackward => "",
-        // NextTrack => "",
-        // NoConvert => "",
-        // NumpadComma => "NumpadComma",
-        // NumpadEnter => "NumpadEnter", // Same as Enter
-        // NumpadEquals => "NumpadEqual",
-        // OEM102 => "",
-        0x34 => "Period",
-        // PlayPause => "",
-        // Power => "",
-        // PrevTrack => "",
-        // RAlt => "",
-        0x1B => "BracketRight",
-        // RControl => "ControlRight", // Same as ControlLeft
-        // RMenu => "AltRight", // Same as ControlLeft
-        0x36 => "ShiftRight",
-        // RWin => "",
-        // 0x27 => "Semicolon",
-        // 0x35 => "Slash", // Same as NumpadDivide
-        // Sleep => "",
-        // Stop => "",
-        0x4A => "NumpadSubtract",
-        // Sysrq => "",
-        0x0F => "Tab",
-        // Underline => "",
-        // Unlabeled => "",
-        // VolumeDown => "",
-        // VolumeUp => "",
-        // Wake => "",
-        // WebBack => "",
-        // WebFavorites => "",
-        // WebForward => "",
-        // WebHome => "",
-        // WebRefresh => "",
-        // WebSearch => "",
-        // WebStop => "",
-        // Yen => "",
-        // Caret => "Caret",
-        _ => "",
+fn find_key_code(platform: Platform, scancode: u32) -> Option<KeyCode> {
+    SCAN_CODES
+        .iter()
+        .find(|entry| platform_scan_code(platform, entry) == scancode)
+        .map(|entry| entry.0)
+}
+
+fn find_scan_code(platform: Platform, key_code: KeyCode) -> Option<u32> {
+    SCAN_CODES
+        .iter()
+        .find(|entry| entry.0 == key_code)
+        .map(|entry| platform_scan_code(platform, entry))
+        .filter(|&scancode| scancode != NONE)
+}
+
+fn platform_key_code(
+    platform: Platform,
+    scancode: u32,
+    virtual_key: Option<VirtualKeyCode>,
+) -> KeyCode {
+    if platform != Platform::Windows {
+        return find_key_code(platform, scancode).unwrap_or(KeyCode::Unidentified(scancode));
+    }
+    // winit drops the 0xE0 prefix of extended keys, for example Home and Numpad7 are both 0x47.
+    // The virtual key tells them apart, except for the numpad keys when num lock is off
+    match (
+        find_key_code(platform, scancode),
+        find_key_code(platform, scancode | 0xE000),
+    ) {
+        (Some(key_code), Some(extended)) => {
+            if virtual_key.map(translate_virtual_key) == Some(extended) {
+                extended
+            } else {
+                key_code
+            }
+        }
+        (key_code, extended) => key_code
+            .or(extended)
+            .unwrap_or(KeyCode::Unidentified(scancode)),
     }
 }
 
-#[cfg(target_os = "linux")]
-pub fn translate_scan_code(c: u32) -> &'static str {
-    match c {
-        0x02 => "Digit1",
-        0x03 => "Digit2",
-        0x04 => "Digit3",
-        0x05 => "Digit4",
-        0x06 => "Digit5",
-        0x07 => "Digit6",
-        0x08 => "Digit7",
-        0x09 => "Digit8",
-        0x0A => "Digit9",
-        0x0B => "Digit0",
-        0x1E => "KeyA",
-        0x30 => "KeyB",
-        0x2E => "KeyC",
-        0x20 => "KeyD",
-        0x12 => "KeyE",
-        0x21 => "KeyF",
-        0x22 => "KeyG",
-        0x23 => "KeyH",
-        0x17 => "KeyI",
-        0x24 => "KeyJ",
-        0x25 => "KeyK",
-        0x26 => "KeyL",
-        0x32 => "KeyM",
-        0x31 => "KeyN",
-        0x18 => "KeyO",
-        0x19 => "KeyP",
-        0x10 => "KeyQ",
-        0x13 => "KeyR",
-        0x1F => "KeyS",
-        0x14 => "KeyT",
-        0x16 => "KeyU",
-        0x2F => "KeyV",
-        0x11 => "KeyW",
-        0x2D => "KeyX",
-        0x15 => "KeyY",
-        0x2C => "KeyZ",
-        0x01 => "Escape",
-        0x3B => "F1",
-        0x3C => "F2",
-        0x3D => "F3",
-        0x3E => "F4",
-        0x3F => "F5",
-        0x40 => "F6",
-        0x41 => "F7",
-        0x42 => "F8",
-        0x43 => "F9",
-        0x44 => "F10",
-        0x85 => "F11",
-        0x86 => "F12",
-        // F13 => "F13",
-        // F14 => "F14",
-        // F15 => "F15",
-        0x37 => "Snapshot",
-        0x46 => "ScrollLock",
-        // Pause => "Pause",
-        0x52 => "Insert",
-        0x47 => "Home",
-        0x53 => "Delete",
-        0x4F => "End",
-        0x6D => "PageDown",
-        0x68 => "PageUp",
-        0x69 => "ArrowLeft",
-        0x67 => "ArrowUp",
-        0x6a => "ArrowRight",
-        0x6c => "ArrowDown",
-        0x0E => "Backspace",
-        0x1C => "Enter",
-        0x39 => "Space",
-        // Compose => "",
-        0x45 => "NumLock",
-        // Numpad0 => "Numpad0", // Same as Insert
-        // Numpad1 => "Numpad1", // Same as End
-        // Numpad2 => "Numpad2", // Same as ArrowDown
-        // Numpad3 => "Numpad3", // Same as PageDown
-        // Numpad4 => "Numpad4", // Same as ArrowLeft
-        0x4C => "Numpad5",
-        // Numpad6 => "Numpad6", // Same as ArrowRight
-        // Numpad7 => "Numpad7", // Same as Home
-        // Numpad8 => "Numpad8", // Same as ArrowUp
-        // Numpad9 => "Numpad9", // Same as PageUp
-        // AbntC1 => "",
-        // AbntC2 => "",
-        0x4E => "NumpadAdd",
-        // Apostrophe => "Backslash",
-        // Apps => "",
-        // At => "",
-        // Ax => "",
-        0x2B => "Backslash",
-        // Calculator => "",
-        0x3A => "CapsLock",
-        0x27 => "Colon",
-        0x33 => "Comma",
-        // Convert => "",
-        // Decimal => "NumpadDecimal", // Same as Delete
-        0x35 => "NumpadDivide",
-        0x0D => "Equal",
-        0x29 => "Backquote",
-        // Kana => "",
-        // Kanji => "",
-        // LAlt => "",
-        0x1A => "BracketLeft",
-        0x1D => "ControlLeft",
-        0x38 => "AltLeft",
-        0x2A => "ShiftLeft",
-        0x5B => "LeftWin",
-        // Mail => "",
-        // MediaSelect => "",
-        // MediaStop => "",
-        0x0C => "Minus",
-        // Multiply => "NumpadMultiply", // Same as Snapshot
-        // Mute => "",
-        // MyComputer => "",
-        // NavigateForward => "",
-        // NavigateBackward => "",
-        // NextTrack => "",
-        // NoConvert => "",
-        // NumpadComma => "NumpadComma",
-        // NumpadEnter => "NumpadEnter", // Same as Enter
-        // NumpadEquals => "NumpadEqual",
-        // OEM102 => "",
-        0x34 => "Period",
-        // PlayPause => "",
-        // Power => "",
-        // PrevTrack => "",
-        // RAlt => "",
-        0x1B => "BracketRight",
-        // RControl => "ControlRight", // Same as ControlLeft
-        // RMenu => "AltRight", // Same as ControlLeft
-        0x36 => "ShiftRight",
-        // RWin => "",
-        // 0x27 => "Semicolon",
-        // 0x35 => "Slash", // Same as NumpadDivide
-        // Sleep => "",
-        // Stop => "",
-        0x4A => "NumpadSubtract",
-        // Sysrq => "",
-        0x0F => "Tab",
-        // Underline => "",
-        // Unlabeled => "",
-        // VolumeDown => "",
-        // VolumeUp => "",
-        // Wake => "",
-        // WebBack => "",
-        // WebFavorites => "",
-        // WebForward => "",
-        // WebHome => "",
-        // WebRefresh => "",
-        // WebSearch => "",
-        // WebStop => "",
-        // Yen => "",
-        // Caret => "Caret",
-        _ => "",
+/// convert a winit scancode to the W3C key code reported by browsers for the same physical key
+pub fn translate_scan_code(scancode: u32, virtual_key: Option<VirtualKeyCode>) -> KeyCode {
+    platform_key_code(PLATFORM, scancode, virtual_key)
+}
+
+/// the native scancode of a key, None if the key doesn't exist on this platform
+pub fn scan_code(key_code: KeyCode) -> Option<u32> {
+    find_scan_code(PLATFORM, key_code)
+}
+
+/// characters produced by the writing system keys of a US keyboard, without modifier
+#[rustfmt::skip]
+const US_LABELS: &[(KeyCode, char)] = &[
+    (KeyCode::Backquote, '`'),
+    (KeyCode::Backslash, '\\'),
+    (KeyCode::BracketLeft, '['),
+    (KeyCode::BracketRight, ']'),
+    (KeyCode::Comma, ','),
+    (KeyCode::Digit0, '0'),
+    (KeyCode::Digit1, '1'),
+    (KeyCode::Digit2, '2'),
+    (KeyCode::Digit3, '3'),
+    (KeyCode::Digit4, '4'),
+    (KeyCode::Digit5, '5'),
+    (KeyCode::Digit6, '6'),
+    (KeyCode::Digit7, '7'),
+    (KeyCode::Digit8, '8'),
+    (KeyCode::Digit9, '9'),
+    (KeyCode::Equal, '='),
+    (KeyCode::KeyA, 'a'),
+    (KeyCode::KeyB, 'b'),
+    (KeyCode::KeyC, 'c'),
+    (KeyCode::KeyD, 'd'),
+    (KeyCode::KeyE, 'e'),
+    (KeyCode::KeyF, 'f'),
+    (KeyCode::KeyG, 'g'),
+    (KeyCode::KeyH, 'h'),
+    (KeyCode::KeyI, 'i'),
+    (KeyCode::KeyJ, 'j'),
+    (KeyCode::KeyK, 'k'),
+    (KeyCode::KeyL, 'l'),
+    (KeyCode::KeyM, 'm'),
+    (KeyCode::KeyN, 'n'),
+    (KeyCode::KeyO, 'o'),
+    (KeyCode::KeyP, 'p'),
+    (KeyCode::KeyQ, 'q'),
+    (KeyCode::KeyR, 'r'),
+    (KeyCode::KeyS, 's'),
+    (KeyCode::KeyT, 't'),
+    (KeyCode::KeyU, 'u'),
+    (KeyCode::KeyV, 'v'),
+    (KeyCode::KeyW, 'w'),
+    (KeyCode::KeyX, 'x'),
+    (KeyCode::KeyY, 'y'),
+    (KeyCode::KeyZ, 'z'),
+    (KeyCode::Minus, '-'),
+    (KeyCode::Period, '.'),
+    (KeyCode::Quote, '\''),
+    (KeyCode::Semicolon, ';'),
+    (KeyCode::Slash, '/'),
+    (KeyCode::Space, ' '),
+];
+
+/// the character produced by a key on a US keyboard, used when the system layout is unknown
+pub fn us_label(key_code: KeyCode) -> Option<char> {
+    US_LABELS
+        .iter()
+        .find(|entry| entry.0 == key_code)
+        .map(|entry| entry.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// keys of a US 104 keys keyboard
+    const US_KEYS: &[KeyCode] = &[
+        KeyCode::Backquote,
+        KeyCode::Backslash,
+        KeyCode::BracketLeft,
+        KeyCode::BracketRight,
+        KeyCode::Comma,
+        KeyCode::Digit0,
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+        KeyCode::Equal,
+        KeyCode::KeyA,
+        KeyCode::KeyB,
+        KeyCode::KeyC,
+        KeyCode::KeyD,
+        KeyCode::KeyE,
+        KeyCode::KeyF,
+        KeyCode::KeyG,
+        KeyCode::KeyH,
+        KeyCode::KeyI,
+        KeyCode::KeyJ,
+        KeyCode::KeyK,
+        KeyCode::KeyL,
+        KeyCode::KeyM,
+        KeyCode::KeyN,
+        KeyCode::KeyO,
+        KeyCode::KeyP,
+        KeyCode::KeyQ,
+        KeyCode::KeyR,
+        KeyCode::KeyS,
+        KeyCode::KeyT,
+        KeyCode::KeyU,
+        KeyCode::KeyV,
+        KeyCode::KeyW,
+        KeyCode::KeyX,
+        KeyCode::KeyY,
+        KeyCode::KeyZ,
+        KeyCode::Minus,
+        KeyCode::Period,
+        KeyCode::Quote,
+        KeyCode::Semicolon,
+        KeyCode::Slash,
+        KeyCode::AltLeft,
+        KeyCode::AltRight,
+        KeyCode::Backspace,
+        KeyCode::CapsLock,
+        KeyCode::ContextMenu,
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::Enter,
+        KeyCode::MetaLeft,
+        KeyCode::MetaRight,
+        KeyCode::ShiftLeft,
+        KeyCode::ShiftRight,
+        KeyCode::Space,
+        KeyCode::Tab,
+        KeyCode::Delete,
+        KeyCode::End,
+        KeyCode::Home,
+        KeyCode::Insert,
+        KeyCode::PageDown,
+        KeyCode::PageUp,
+        KeyCode::ArrowDown,
+        KeyCode::ArrowLeft,
+        KeyCode::ArrowRight,
+        KeyCode::ArrowUp,
+        KeyCode::NumLock,
+        KeyCode::Numpad0,
+        KeyCode::Numpad1,
+        KeyCode::Numpad2,
+        KeyCode::Numpad3,
+        KeyCode::Numpad4,
+        KeyCode::Numpad5,
+        KeyCode::Numpad6,
+        KeyCode::Numpad7,
+        KeyCode::Numpad8,
+        KeyCode::Numpad9,
+        KeyCode::NumpadAdd,
+        KeyCode::NumpadDecimal,
+        KeyCode::NumpadDivide,
+        KeyCode::NumpadEnter,
+        KeyCode::NumpadMultiply,
+        KeyCode::NumpadSubtract,
+        KeyCode::Escape,
+        KeyCode::F1,
+        KeyCode::F2,
+        KeyCode::F3,
+        KeyCode::F4,
+        KeyCode::F5,
+        KeyCode::F6,
+        KeyCode::F7,
+        KeyCode::F8,
+        KeyCode::F9,
+        KeyCode::F10,
+        KeyCode::F11,
+        KeyCode::F12,
+        KeyCode::PrintScreen,
+        KeyCode::ScrollLock,
+        KeyCode::Pause,
+    ];
+
+    /// additional key of ISO keyboards
+    const ISO_KEYS: &[KeyCode] = &[KeyCode::IntlBackslash];
+
+    /// additional keys of JIS keyboards
+    const JIS_KEYS: &[KeyCode] = &[
+        KeyCode::IntlRo,
+        KeyCode::IntlYen,
+        KeyCode::Convert,
+        KeyCode::NonConvert,
+        KeyCode::KanaMode,
+        KeyCode::NumpadComma,
+    ];
+
+    /// mac keyboards have F13-F15 instead of print screen, scroll lock and pause,
+    /// and the eisu/kana keys (Lang2/Lang1) instead of the conversion keys
+    const MACOS_MISSING: &[KeyCode] = &[
+        KeyCode::PrintScreen,
+        KeyCode::ScrollLock,
+        KeyCode::Pause,
+        KeyCode::Convert,
+        KeyCode::NonConvert,
+        KeyCode::KanaMode,
+    ];
+
+    const PLATFORMS: &[Platform] = &[Platform::MacOs, Platform::Windows, Platform::Linux];
+
+    fn check_coverage(platform: Platform, keys: &[KeyCode], missing: &[KeyCode]) {
+        for key_code in keys.iter().filter(|k| !missing.contains(k)) {
+            assert!(
+                find_scan_code(platform, *key_code).is_some(),
+                "{:?} has no scancode for {}",
+                platform,
+                key_code
+            );
+        }
+    }
+
+    #[test]
+    fn us_keys() {
+        check_coverage(Platform::MacOs, US_KEYS, MACOS_MISSING);
+        check_coverage(Platform::Windows, US_KEYS, &[]);
+        check_coverage(Platform::Linux, US_KEYS, &[]);
+    }
+
+    #[test]
+    fn iso_keys() {
+        for &platform in PLATFORMS {
+            check_coverage(platform, ISO_KEYS, &[]);
+        }
+    }
+
+    #[test]
+    fn jis_keys() {
+        check_coverage(Platform::MacOs, JIS_KEYS, MACOS_MISSING);
+        check_coverage(Platform::MacOs, &[KeyCode::Lang1, KeyCode::Lang2], &[]);
+        check_coverage(Platform::Windows, JIS_KEYS, &[]);
+        check_coverage(Platform::Linux, JIS_KEYS, &[]);
+    }
+
+    #[test]
+    fn table_has_every_key_code_once() {
+        for entry in SCAN_CODES {
+            let count = SCAN_CODES.iter().filter(|e| e.0 == entry.0).count();
+            assert_eq!(count, 1, "{} is in the table {} times", entry.0, count);
+        }
+        for key_code in ::keycode::KEY_CODES {
+            assert!(
+                SCAN_CODES.iter().any(|e| e.0 == *key_code),
+                "{} is missing from the table",
+                key_code
+            );
+        }
+    }
+
+    #[test]
+    fn scancodes_are_unique() {
+        for &platform in PLATFORMS {
+            for entry in SCAN_CODES {
+                let scancode = platform_scan_code(platform, entry);
+                if scancode != NONE {
+                    assert_eq!(
+                        find_key_code(platform, scancode),
+                        Some(entry.0),
+                        "{:?} scancode {:#x} is used by several keys",
+                        platform,
+                        scancode
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        for &platform in PLATFORMS {
+            for entry in SCAN_CODES {
+                let key_code = entry.0;
+                if let Some(scancode) = find_scan_code(platform, key_code) {
+                    assert_eq!(
+                        platform_key_code(platform, scancode, None),
+                        key_code,
+                        "{:?} scancode {:#x}",
+                        platform,
+                        scancode
+                    );
+                }
+                // the code string is the one reported by browsers
+                assert_eq!(key_code.to_string().parse(), Ok(key_code));
+            }
+        }
+    }
+
+    #[test]
+    fn windows_extended_keys() {
+        let key_code = |scancode, virtual_key| {
+            platform_key_code(Platform::Windows, scancode, Some(virtual_key))
+        };
+        assert_eq!(key_code(0x47, VirtualKeyCode::Home), KeyCode::Home);
+        assert_eq!(key_code(0x47, VirtualKeyCode::Numpad7), KeyCode::Numpad7);
+        assert_eq!(key_code(0x1C, VirtualKeyCode::Return), KeyCode::Enter);
+        assert_eq!(
+            key_code(0x1C, VirtualKeyCode::NumpadEnter),
+            KeyCode::NumpadEnter
+        );
+        assert_eq!(
+            key_code(0x1D, VirtualKeyCode::RControl),
+            KeyCode::ControlRight
+        );
+        assert_eq!(key_code(0x5B, VirtualKeyCode::LWin), KeyCode::MetaLeft);
+        assert_eq!(
+            key_code(0x37, VirtualKeyCode::Snapshot),
+            KeyCode::PrintScreen
+        );
+        assert_eq!(
+            key_code(0x37, VirtualKeyCode::Multiply),
+            KeyCode::NumpadMultiply
+        );
     }
 }
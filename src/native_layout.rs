@@ -0,0 +1,93 @@
+#[cfg(target_os = "linux")]
+pub use self::x11::KeyboardLayout;
+
+#[cfg(not(target_os = "linux"))]
+pub use self::unsupported::KeyboardLayout;
+
+/// linux backend, using the XKB keymap of the X server. Not available when the window uses wayland
+#[cfg(target_os = "linux")]
+mod x11 {
+    use glutin;
+    use glutin::os::unix::WindowExt;
+    use std::os::raw::c_uint;
+    use x11_dl::xlib;
+
+    use super::super::native_keycode::{scan_code, us_label};
+    use KeyCode;
+
+    /// the core keyboard device
+    const XKB_USE_CORE_KBD: c_uint = 0x0100;
+
+    pub struct KeyboardLayout {
+        xlib: Option<xlib::Xlib>,
+    }
+
+    impl KeyboardLayout {
+        pub fn new() -> KeyboardLayout {
+            KeyboardLayout {
+                xlib: xlib::Xlib::open().ok(),
+            }
+        }
+
+        /// the character produced by the key in the current keyboard group, without modifier.
+        /// Uses a US layout without X11 window
+        pub fn label(&self, window: Option<&glutin::Window>, key_code: KeyCode) -> Option<String> {
+            match (&self.xlib, window.and_then(|w| w.get_xlib_display())) {
+                (Some(xlib), Some(display)) => {
+                    let display = display as *mut xlib::Display;
+                    // X11 keycodes are the evdev codes + 8
+                    let keycode = scan_code(key_code)? + 8;
+                    if keycode > 255 {
+                        return None;
+                    }
+                    let keysym = unsafe {
+                        let mut state: xlib::XkbStateRec = ::std::mem::zeroed();
+                        (xlib.XkbGetState)(display, XKB_USE_CORE_KBD, &mut state);
+                        (xlib.XkbKeycodeToKeysym)(display, keycode as u8, i32::from(state.group), 0)
+                    };
+                    keysym_to_char(keysym).map(|c| c.to_string())
+                }
+                _ => us_label(key_code).map(|c| c.to_string()),
+            }
+        }
+    }
+
+    /// the character of a keysym. Dead keys return their accent
+    fn keysym_to_char(keysym: xlib::KeySym) -> Option<char> {
+        let code = match keysym {
+            // latin 1 keysyms are the unicode code points
+            0x20..=0x7e | 0xa0..=0xff => keysym as u32,
+            // unicode keysyms
+            0x0100_0100..=0x0110_ffff => (keysym & 0x00ff_ffff) as u32,
+            // dead_grave, dead_acute, dead_circumflex, dead_tilde, dead_diaeresis
+            0xfe50 => 0x60,
+            0xfe51 => 0xb4,
+            0xfe52 => 0x5e,
+            0xfe53 => 0x7e,
+            0xfe57 => 0xa8,
+            _ => return None,
+        };
+        ::std::char::from_u32(code)
+    }
+}
+
+/// other operating systems use a US layout
+#[cfg(not(target_os = "linux"))]
+mod unsupported {
+    use glutin;
+
+    use super::super::native_keycode::us_label;
+    use KeyCode;
+
+    pub struct KeyboardLayout {}
+
+    impl KeyboardLayout {
+        pub fn new() -> KeyboardLayout {
+            KeyboardLayout {}
+        }
+
+        pub fn label(&self, _window: Option<&glutin::Window>, key_code: KeyCode) -> Option<String> {
+            us_label(key_code).map(|c| c.to_string())
+        }
+    }
+}
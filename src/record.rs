@@ -0,0 +1,771 @@
+use std::cell::Cell;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use events::*;
+use {App, AppEvent, File, FileSystem, IoError, KeyCode};
+use {GamepadAxis, GamepadAxisEvent, GamepadButton, GamepadButtonEvent};
+
+/// recording file header, followed by the format version
+const MAGIC: &[u8] = b"UNIREC";
+const VERSION: u8 = 1;
+
+/// delay in seconds between two writes of the new frames in the recording file.
+/// On web target, the local storage can only replace a whole entry : the recording is only
+/// written when the page is hidden
+#[cfg(not(target_arch = "wasm32"))]
+const SAVE_DELAY: Option<f64> = Some(1.0);
+#[cfg(target_arch = "wasm32")]
+const SAVE_DELAY: Option<f64> = None;
+
+thread_local! {
+    /// time of the current frame while replaying a recording
+    static REPLAY_TIME: Cell<Option<f64>> = const { Cell::new(None) };
+}
+
+/// the time returned by `now()` while replaying a recording
+pub(crate) fn replay_time() -> Option<f64> {
+    REPLAY_TIME.with(Cell::get)
+}
+
+/// value of a `--name=value` command line parameter or `name=value` URL parameter
+pub(crate) fn get_param(params: &[String], name: &str) -> Option<String> {
+    params.iter().find_map(|p| {
+        let p = p.trim_start_matches("--");
+        if p.len() > name.len() && p.starts_with(name) && p[name.len()..].starts_with('=') {
+            Some(p[name.len() + 1..].to_owned())
+        } else {
+            None
+        }
+    })
+}
+
+fn invalid_data(msg: &str) -> IoError {
+    IoError::new(
+        ErrorKind::InvalidData,
+        format!("invalid recording : {}", msg),
+    )
+}
+
+struct Encoder(Vec<u8>);
+
+impl Encoder {
+    fn u8(&mut self, v: u8) {
+        self.0.push(v);
+    }
+
+    fn bool(&mut self, v: bool) {
+        self.0.push(v as u8);
+    }
+
+    /// LEB128 variable length integer
+    fn varint(&mut self, mut v: u64) {
+        while v >= 0x80 {
+            self.0.push((v as u8) | 0x80);
+            v >>= 7;
+        }
+        self.0.push(v as u8);
+    }
+
+    fn i32(&mut self, v: i32) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn f32(&mut self, v: f32) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn f64(&mut self, v: f64) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn str(&mut self, v: &str) {
+        self.varint(v.len() as u64);
+        self.0.extend_from_slice(v.as_bytes());
+    }
+
+    fn option_str(&mut self, v: Option<&str>) {
+        self.bool(v.is_some());
+        if let Some(v) = v {
+            self.str(v);
+        }
+    }
+
+    fn option_path(&mut self, path: &Option<PathBuf>) {
+        self.option_str(
+            path.as_ref()
+                .map(|p| p.to_string_lossy())
+                .as_ref()
+                .map(|p| &p[..]),
+        );
+    }
+
+    fn pos(&mut self, pos: (f64, f64)) {
+        self.f64(pos.0);
+        self.f64(pos.1);
+    }
+
+    fn key_code(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Unidentified(scancode) => {
+                self.u8(0);
+                self.varint(u64::from(scancode));
+            }
+            _ => {
+                self.u8(1);
+                self.str(code.name());
+            }
+        }
+    }
+
+    fn key(
+        &mut self,
+        code: &str,
+        key_code: KeyCode,
+        key: &str,
+        modifiers: [bool; 4],
+        repeat: bool,
+    ) {
+        self.str(code);
+        self.key_code(key_code);
+        self.str(key);
+        for m in &modifiers {
+            self.bool(*m);
+        }
+        self.bool(repeat);
+    }
+
+    fn event(&mut self, evt: &AppEvent) {
+        match *evt {
+            AppEvent::MouseDown(ref e) => {
+                self.u8(0);
+                self.varint(e.button as u64);
+            }
+            AppEvent::MouseUp(ref e) => {
+                self.u8(1);
+                self.varint(e.button as u64);
+            }
+            AppEvent::MouseWheel(ref e) => {
+                self.u8(2);
+                self.f32(e.line_x);
+                self.f32(e.line_y);
+                self.f64(e.pixel_x);
+                self.f64(e.pixel_y);
+            }
+            AppEvent::KeyDown(ref e) => {
+                self.u8(3);
+                self.key(
+                    &e.code,
+                    e.key_code,
+                    &e.key,
+                    [e.shift, e.alt, e.ctrl, e.logo],
+                    e.repeat,
+                );
+            }
+            AppEvent::KeyUp(ref e) => {
+                self.u8(4);
+                self.key(
+                    &e.code,
+                    e.key_code,
+                    &e.key,
+                    [e.shift, e.alt, e.ctrl, e.logo],
+                    e.repeat,
+                );
+            }
+            AppEvent::CharEvent(c) => {
+                self.u8(5);
+                self.varint(u64::from(c as u32));
+            }
+            AppEvent::Resized((w, h)) => {
+                self.u8(6);
+                self.varint(u64::from(w));
+                self.varint(u64::from(h));
+            }
+            AppEvent::MousePos(pos) => {
+                self.u8(7);
+                self.pos(pos);
+            }
+            AppEvent::MouseMotion(ref e) => {
+                self.u8(8);
+                self.pos((e.dx, e.dy));
+            }
+            AppEvent::PointerLockChanged(b) => {
+                self.u8(9);
+                self.bool(b);
+            }
+            AppEvent::TouchStart(ref e) => {
+                self.u8(10);
+                self.varint(e.id);
+                self.pos(e.pos);
+            }
+            AppEvent::TouchMove(ref e) => {
+                self.u8(11);
+                self.varint(e.id);
+                self.pos(e.pos);
+            }
+            AppEvent::TouchEnd(ref e) => {
+                self.u8(12);
+                self.varint(e.id);
+                self.pos(e.pos);
+            }
+            AppEvent::TouchCancel(ref e) => {
+                self.u8(13);
+                self.varint(e.id);
+                self.pos(e.pos);
+            }
+            AppEvent::FileDropped(ref e) => {
+                self.u8(14);
+                self.str(&e.name);
+                self.varint(e.size);
+                self.option_str(e.mime_type.as_ref().map(|m| &m[..]));
+                self.option_path(&e.path);
+            }
+            AppEvent::CloseRequested => self.u8(15),
+            AppEvent::FocusGained => self.u8(16),
+            AppEvent::FocusLost => self.u8(17),
+            AppEvent::CursorEntered => self.u8(18),
+            AppEvent::CursorLeft => self.u8(19),
+            AppEvent::Moved((x, y)) => {
+                self.u8(20);
+                self.i32(x);
+                self.i32(y);
+            }
+            AppEvent::ScaleFactorChanged(factor) => {
+                self.u8(21);
+                self.f32(factor);
+            }
+            AppEvent::ClipboardText(ref text) => {
+                self.u8(22);
+                self.str(text);
+            }
+            AppEvent::GamepadConnected(id) => {
+                self.u8(23);
+                self.varint(id as u64);
+            }
+            AppEvent::GamepadDisconnected(id) => {
+                self.u8(24);
+                self.varint(id as u64);
+            }
+            AppEvent::GamepadButtonDown(ref e) => {
+                self.u8(25);
+                self.varint(e.gamepad as u64);
+                self.u8(e.button as u8);
+            }
+            AppEvent::GamepadButtonUp(ref e) => {
+                self.u8(26);
+                self.varint(e.gamepad as u64);
+                self.u8(e.button as u8);
+            }
+            AppEvent::GamepadAxis(ref e) => {
+                self.u8(27);
+                self.varint(e.gamepad as u64);
+                self.u8(e.axis as u8);
+                self.f32(e.value);
+            }
+            AppEvent::FileHovered(ref path) => {
+                self.u8(28);
+                self.option_path(path);
+            }
+            AppEvent::FileHoverCancelled => self.u8(29),
+            AppEvent::TextComposition(ref e) => {
+                self.u8(30);
+                self.str(&e.text);
+                self.varint(e.cursor as u64);
+            }
+            AppEvent::TextCommit(ref text) => {
+                self.u8(31);
+                self.str(text);
+            }
+            AppEvent::FullscreenChanged(b) => {
+                self.u8(32);
+                self.bool(b);
+            }
+        }
+    }
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], IoError> {
+        // len comes from the file and can be anything
+        if len > self.data.len() - self.pos {
+            return Err(invalid_data("unexpected end of file"));
+        }
+        let bytes = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn array<T: Default + AsMut<[u8]>>(&mut self) -> Result<T, IoError> {
+        let mut array = T::default();
+        let len = array.as_mut().len();
+        array.as_mut().copy_from_slice(self.bytes(len)?);
+        Ok(array)
+    }
+
+    fn u8(&mut self) -> Result<u8, IoError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn bool(&mut self) -> Result<bool, IoError> {
+        Ok(self.u8()? != 0)
+    }
+
+    fn varint(&mut self) -> Result<u64, IoError> {
+        let mut v = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = self.u8()?;
+            // the 10th byte only has room for the 64th bit
+            if shift == 63 && b & 0x7e != 0 {
+                return Err(invalid_data("integer overflow"));
+            }
+            v |= u64::from(b & 0x7f) << shift;
+            if b & 0x80 == 0 {
+                return Ok(v);
+            }
+        }
+        Err(invalid_data("integer overflow"))
+    }
+
+    fn usize(&mut self) -> Result<usize, IoError> {
+        Ok(self.varint()? as usize)
+    }
+
+    fn i32(&mut self) -> Result<i32, IoError> {
+        Ok(i32::from_le_bytes(self.array()?))
+    }
+
+    fn f32(&mut self) -> Result<f32, IoError> {
+        Ok(f32::from_le_bytes(self.array()?))
+    }
+
+    fn f64(&mut self) -> Result<f64, IoError> {
+        Ok(f64::from_le_bytes(self.array()?))
+    }
+
+    fn str(&mut self) -> Result<String, IoError> {
+        let len = self.usize()?;
+        String::from_utf8(self.bytes(len)?.to_vec()).map_err(|_| invalid_data("invalid string"))
+    }
+
+    fn option_str(&mut self) -> Result<Option<String>, IoError> {
+        if self.bool()? {
+            Ok(Some(self.str()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn option_path(&mut self) -> Result<Option<PathBuf>, IoError> {
+        Ok(self.option_str()?.map(PathBuf::from))
+    }
+
+    fn pos(&mut self) -> Result<(f64, f64), IoError> {
+        Ok((self.f64()?, self.f64()?))
+    }
+
+    fn key_code(&mut self) -> Result<KeyCode, IoError> {
+        if self.u8()? == 0 {
+            Ok(KeyCode::Unidentified(self.varint()? as u32))
+        } else {
+            self.str()?
+                .parse()
+                .map_err(|_| invalid_data("unknown key code"))
+        }
+    }
+
+    fn key_down(&mut self) -> Result<KeyDownEvent, IoError> {
+        Ok(KeyDownEvent {
+            code: self.str()?,
+            key_code: self.key_code()?,
+            key: self.str()?,
+            shift: self.bool()?,
+            alt: self.bool()?,
+            ctrl: self.bool()?,
+            logo: self.bool()?,
+            repeat: self.bool()?,
+        })
+    }
+
+    fn touch(&mut self) -> Result<TouchEvent, IoError> {
+        Ok(TouchEvent {
+            id: self.varint()?,
+            pos: self.pos()?,
+        })
+    }
+
+    fn gamepad_button(&mut self) -> Result<GamepadButtonEvent, IoError> {
+        let gamepad = self.usize()?;
+        let button = GamepadButton::from_index(self.u8()? as usize)
+            .ok_or_else(|| invalid_data("unknown gamepad button"))?;
+        Ok(GamepadButtonEvent { gamepad, button })
+    }
+
+    fn event(&mut self) -> Result<AppEvent, IoError> {
+        Ok(match self.u8()? {
+            0 => AppEvent::MouseDown(MouseButtonEvent {
+                button: self.usize()?,
+            }),
+            1 => AppEvent::MouseUp(MouseButtonEvent {
+                button: self.usize()?,
+            }),
+            2 => AppEvent::MouseWheel(MouseWheelEvent {
+                line_x: self.f32()?,
+                line_y: self.f32()?,
+                pixel_x: self.f64()?,
+                pixel_y: self.f64()?,
+            }),
+            3 => AppEvent::KeyDown(self.key_down()?),
+            4 => {
+                let e = self.key_down()?;
+                AppEvent::KeyUp(KeyUpEvent {
+                    code: e.code,
+                    key_code: e.key_code,
+                    key: e.key,
+                    shift: e.shift,
+                    alt: e.alt,
+                    ctrl: e.ctrl,
+                    logo: e.logo,
+                    repeat: e.repeat,
+                })
+            }
+            5 => AppEvent::CharEvent(
+                ::std::char::from_u32(self.varint()? as u32)
+                    .ok_or_else(|| invalid_data("invalid character"))?,
+            ),
+            6 => AppEvent::Resized((self.varint()? as u32, self.varint()? as u32)),
+            7 => AppEvent::MousePos(self.pos()?),
+            8 => {
+                let (dx, dy) = self.pos()?;
+                AppEvent::MouseMotion(MouseMotionEvent { dx, dy })
+            }
+            9 => AppEvent::PointerLockChanged(self.bool()?),
+            10 => AppEvent::TouchStart(self.touch()?),
+            11 => AppEvent::TouchMove(self.touch()?),
+            12 => AppEvent::TouchEnd(self.touch()?),
+            13 => AppEvent::TouchCancel(self.touch()?),
+            14 => AppEvent::FileDropped(FileDroppedEvent {
+                name: self.str()?,
+                size: self.varint()?,
+                mime_type: self.option_str()?,
+                path: self.option_path()?,
+            }),
+            15 => AppEvent::CloseRequested,
+            16 => AppEvent::FocusGained,
+            17 => AppEvent::FocusLost,
+            18 => AppEvent::CursorEntered,
+            19 => AppEvent::CursorLeft,
+            20 => AppEvent::Moved((self.i32()?, self.i32()?)),
+            21 => AppEvent::ScaleFactorChanged(self.f32()?),
+            22 => AppEvent::ClipboardText(self.str()?),
+            23 => AppEvent::GamepadConnected(self.usize()?),
+            24 => AppEvent::GamepadDisconnected(self.usize()?),
+            25 => AppEvent::GamepadButtonDown(self.gamepad_button()?),
+            26 => AppEvent::GamepadButtonUp(self.gamepad_button()?),
+            27 => {
+                let gamepad = self.usize()?;
+                let axis = GamepadAxis::from_index(self.u8()? as usize)
+                    .ok_or_else(|| invalid_data("unknown gamepad axis"))?;
+                AppEvent::GamepadAxis(GamepadAxisEvent {
+                    gamepad,
+                    axis,
+                    value: self.f32()?,
+                })
+            }
+            28 => AppEvent::FileHovered(self.option_path()?),
+            29 => AppEvent::FileHoverCancelled,
+            30 => AppEvent::TextComposition(TextCompositionEvent {
+                text: self.str()?,
+                cursor: self.usize()?,
+            }),
+            31 => AppEvent::TextCommit(self.str()?),
+            32 => AppEvent::FullscreenChanged(self.bool()?),
+            _ => return Err(invalid_data("unknown event type")),
+        })
+    }
+}
+
+/// writes the events of every frame in a file, see [`AppConfig.record`]
+pub(crate) struct Recorder {
+    path: String,
+    /// the frames not written in the file yet (native target) or the whole recording (web target)
+    data: Encoder,
+    /// number of bytes written in the file
+    saved: usize,
+    last_time: f64,
+    next_save: f64,
+}
+
+impl Recorder {
+    pub(crate) fn new(path: &str, time: f64) -> Recorder {
+        let mut data = Encoder(MAGIC.to_vec());
+        data.u8(VERSION);
+        data.f64(time);
+        Recorder {
+            path: path.to_owned(),
+            data,
+            saved: 0,
+            last_time: time,
+            next_save: time,
+        }
+    }
+
+    /// add a frame to the recording
+    pub(crate) fn record(&mut self, time: f64, events: &[AppEvent]) {
+        self.data.f64(time - self.last_time);
+        self.last_time = time;
+        self.data.varint(events.len() as u64);
+        for evt in events {
+            self.data.event(evt);
+        }
+        if let Some(delay) = SAVE_DELAY {
+            if time >= self.next_save {
+                self.next_save = time + delay;
+                self.flush();
+            }
+        }
+    }
+
+    /// write the frames recorded since the previous flush
+    pub(crate) fn flush(&mut self) {
+        if let Err(e) = self.write() {
+            App::print(format!(
+                "Could not save the recording {} : {}\n",
+                self.path, e
+            ));
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn write(&mut self) -> Result<(), IoError> {
+        if self.data.0.is_empty() {
+            return Ok(());
+        }
+        if self.saved == 0 {
+            FileSystem::write(&self.path, &self.data.0)?;
+        } else {
+            FileSystem::append(&self.path, &self.data.0)?;
+        }
+        self.saved += self.data.0.len();
+        self.data.0.clear();
+        Ok(())
+    }
+
+    /// the local storage can only replace a whole entry : the recording is kept in memory
+    #[cfg(target_arch = "wasm32")]
+    fn write(&mut self) -> Result<(), IoError> {
+        if self.saved < self.data.0.len() {
+            FileSystem::write(&self.path, &self.data.0)?;
+            self.saved = self.data.0.len();
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// feeds the events of a recording instead of the real input, see [`AppConfig.replay`]
+pub(crate) struct Replay {
+    /// the recording file, until it is loaded
+    file: Option<File>,
+    data: Vec<u8>,
+    pos: usize,
+    time: f64,
+}
+
+impl Replay {
+    pub(crate) fn open(path: &str) -> Result<Replay, IoError> {
+        Ok(Replay {
+            file: Some(FileSystem::open(path)?),
+            data: Vec::new(),
+            pos: 0,
+            time: 0.0,
+        })
+    }
+
+    /// push the events of the next recorded frame.
+    /// Returns false once the whole recording has been replayed
+    pub(crate) fn next_frame(&mut self, events: &mut Vec<AppEvent>) -> bool {
+        match self.read_frame(events) {
+            Ok(running) => running,
+            Err(e) => {
+                App::print(format!("Replay stopped : {}\n", e));
+                false
+            }
+        }
+    }
+
+    fn read_frame(&mut self, events: &mut Vec<AppEvent>) -> Result<bool, IoError> {
+        if let Some(mut file) = self.file.take() {
+            if !file.is_ready() {
+                // web target : still downloading
+                self.file = Some(file);
+                return Ok(true);
+            }
+            self.data = file.read_binary()?;
+            let mut header = Decoder {
+                data: &self.data,
+                pos: 0,
+            };
+            if header.bytes(MAGIC.len())? != MAGIC || header.u8()? != VERSION {
+                return Err(invalid_data("unknown file format"));
+            }
+            self.time = header.f64()?;
+            self.pos = header.pos;
+        }
+        if self.pos >= self.data.len() {
+            REPLAY_TIME.with(|t| t.set(None));
+            return Ok(false);
+        }
+        let mut frame = Decoder {
+            data: &self.data,
+            pos: self.pos,
+        };
+        self.time += frame.f64()?;
+        for _ in 0..frame.usize()? {
+            events.push(frame.event()?);
+        }
+        self.pos = frame.pos;
+        REPLAY_TIME.with(|t| t.set(Some(self.time)));
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events() -> Vec<AppEvent> {
+        vec![
+            AppEvent::MouseDown(MouseButtonEvent { button: 2 }),
+            AppEvent::MouseWheel(MouseWheelEvent {
+                line_x: 0.0,
+                line_y: -1.5,
+                pixel_x: 3.0,
+                pixel_y: 0.0,
+            }),
+            AppEvent::KeyDown(KeyDownEvent {
+                code: "KeyQ".to_owned(),
+                key_code: KeyCode::KeyQ,
+                key: "a".to_owned(),
+                shift: true,
+                alt: false,
+                ctrl: true,
+                logo: false,
+                repeat: true,
+            }),
+            AppEvent::KeyUp(KeyUpEvent {
+                code: "Unidentified".to_owned(),
+                key_code: KeyCode::Unidentified(300),
+                key: String::new(),
+                shift: false,
+                alt: true,
+                ctrl: false,
+                logo: true,
+                repeat: false,
+            }),
+            AppEvent::CharEvent('é'),
+            AppEvent::Resized((1920, 1080)),
+            AppEvent::MousePos((12.5, -3.0)),
+            AppEvent::TouchMove(TouchEvent {
+                id: u64::MAX,
+                pos: (1.0, 2.0),
+            }),
+            AppEvent::FileDropped(FileDroppedEvent {
+                name: "level.json".to_owned(),
+                size: 1234,
+                mime_type: Some("application/json".to_owned()),
+                path: Some(PathBuf::from("/tmp/level.json")),
+            }),
+            AppEvent::Moved((-10, 20)),
+            AppEvent::GamepadAxis(GamepadAxisEvent {
+                gamepad: 1,
+                axis: GamepadAxis::LeftStickX,
+                value: -0.25,
+            }),
+            AppEvent::FileHovered(None),
+            AppEvent::TextComposition(TextCompositionEvent {
+                text: "日本".to_owned(),
+                cursor: 1,
+            }),
+            AppEvent::FullscreenChanged(true),
+        ]
+    }
+
+    fn encode(events: &[AppEvent]) -> Vec<u8> {
+        let mut encoder = Encoder(Vec::new());
+        encoder.varint(events.len() as u64);
+        for evt in events {
+            encoder.event(evt);
+        }
+        encoder.0
+    }
+
+    fn decode(data: &[u8]) -> Result<Vec<AppEvent>, IoError> {
+        let mut decoder = Decoder { data, pos: 0 };
+        let mut events = Vec::new();
+        for _ in 0..decoder.usize()? {
+            events.push(decoder.event()?);
+        }
+        assert_eq!(decoder.pos, data.len());
+        Ok(events)
+    }
+
+    #[test]
+    fn round_trip() {
+        let events = events();
+        assert_eq!(decode(&encode(&events)).unwrap(), events);
+    }
+
+    #[test]
+    fn truncated_input() {
+        let data = encode(&events());
+        for len in 0..data.len() {
+            assert!(decode(&data[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn invalid_lengths() {
+        // a string longer than the remaining data
+        let mut encoder = Encoder(Vec::new());
+        encoder.u8(22);
+        encoder.varint(u64::MAX);
+        let mut decoder = Decoder {
+            data: &encoder.0,
+            pos: 0,
+        };
+        assert!(decoder.event().is_err());
+        // a varint longer than 64 bits
+        let data = [0xff; 11];
+        let mut decoder = Decoder {
+            data: &data,
+            pos: 0,
+        };
+        assert!(decoder.varint().is_err());
+        // a 10 bytes varint with bits past the 64th
+        let mut data = [0xff; 10];
+        data[9] = 0x02;
+        let mut decoder = Decoder {
+            data: &data,
+            pos: 0,
+        };
+        assert!(decoder.varint().is_err());
+        // u64::MAX is still valid
+        let mut encoder = Encoder(Vec::new());
+        encoder.varint(u64::MAX);
+        let mut decoder = Decoder {
+            data: &encoder.0,
+            pos: 0,
+        };
+        assert_eq!(decoder.varint().unwrap(), u64::MAX);
+    }
+}
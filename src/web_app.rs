@@ -1,31 +1,114 @@
+mod web_gamepad;
+
 use stdweb;
 use AppConfig;
 
 use stdweb::traits::{IDragEvent, IEvent};
 use stdweb::unstable::TryInto;
 use stdweb::web::event::{
-    DragDropEvent, IKeyboardEvent, IMouseEvent, KeyDownEvent, KeyUpEvent, MouseButton,
-    MouseDownEvent, MouseMoveEvent, MouseUpEvent, ResizeEvent,
+    BlurEvent, ConcreteEvent, DragDropEvent, DragEnterEvent, DragLeaveEvent, FocusEvent,
+    IFocusEvent, IKeyboardEvent, IMouseEvent, ITouchEvent, KeyDownEvent, KeyUpEvent, MouseButton,
+    MouseDownEvent, MouseEnterEvent, MouseLeaveEvent, MouseMoveEvent, MouseUpEvent,
+    MouseWheelDeltaMode, MouseWheelEvent, PointerLockChangeEvent, TouchCancel, TouchEnd, TouchMove,
+    TouchStart,
 };
 use stdweb::web::html_element::CanvasElement;
-use stdweb::web::{window, FileReader, IEventTarget, IHtmlElement, TypedArray};
+use stdweb::web::{
+    document, window, EventTarget, HtmlElement, IBlob, IEventTarget, IHtmlElement, TypedArray,
+};
+use stdweb::Reference;
 
 use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
 use std::rc::Rc;
 
-use crate::{BufferState, File};
+use self::web_gamepad::GamepadManager;
+use crate::cursor::check_cursor_image;
+use crate::handler::{exit_requested, handle_frame, set_exit_requested, FnHandler};
+use crate::keycode::KEY_CODES;
+use crate::record::{get_param, replay_time, Recorder, Replay};
+use crate::{
+    AppHandler, BufferState, CanvasSizing, CursorIcon, File, GamepadState, InputState, KeyCode,
+    Monitor, PreventDefault, TouchMouseEmulation, WindowMode,
+};
 use AppEvent;
 
 pub struct App {
     window: CanvasElement,
     pub events: Rc<RefCell<Vec<AppEvent>>>,
     device_pixel_ratio: f32,
-    dropped_files: Rc<RefCell<Vec<File>>>,
+    /// None when the canvas is not in the page (headless and null backends)
+    sizing: Option<CanvasSizing>,
+    /// `AppConfig.size`, in CSS pixels
+    size: (u32, u32),
+    dropped_files: Rc<RefCell<VecDeque<File>>>,
+    touch_emulation: Option<Rc<RefCell<TouchMouseEmulation>>>,
+    gamepads: GamepadManager,
+    input: InputState,
+    gesture_requests: Rc<RefCell<Vec<GestureRequest>>>,
+    /// CSS cursor property used when the cursor is visible
+    cursor: String,
+    cursor_visible: bool,
+    /// events added with `inject_event`, delivered on next frame
+    injected: Vec<AppEvent>,
+    /// hidden element receiving the keyboard input while text input is active
+    text_input: HtmlElement,
+    /// characters produced by the keys, by code name
+    keyboard_layout: Rc<RefCell<HashMap<String, String>>>,
+    prevent_default: Rc<PreventDefault>,
+    /// the keyboard listeners are on the document instead of the canvas
+    listen_on_document: bool,
+    recorder: Option<Recorder>,
+    replay: Option<Replay>,
 }
 
 use super::events;
 
+/// browser features that can only be requested from a user input event handler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GestureRequest {
+    PointerLock,
+    Fullscreen,
+}
+
+// listener executing the pending gesture requests
+fn gesture_listener<T: ConcreteEvent>(
+    canvas: &CanvasElement,
+    requests: &Rc<RefCell<Vec<GestureRequest>>>,
+) -> impl FnMut(T) + 'static {
+    let canvas = canvas.clone();
+    let requests = requests.clone();
+    move |_: T| {
+        for request in requests.borrow_mut().drain(..) {
+            match request {
+                GestureRequest::PointerLock => js! { @{&canvas}.requestPointerLock(); },
+                GestureRequest::Fullscreen => js! {
+                    var c = @{&canvas};
+                    if (c.requestFullscreen) {
+                        c.requestFullscreen();
+                    } else if (c.webkitRequestFullscreen) {
+                        c.webkitRequestFullscreen();
+                    }
+                },
+            };
+        }
+    }
+}
+
 macro_rules! map_event {
+    // the default action is prevented according to the PreventDefault policy
+    ($events:expr, $policy:ident, $x:ident, $y:ident, $ee:ident, $e:expr) => {{
+        let events = $events.clone();
+        let policy = $policy.clone();
+        move |$ee: $x| {
+            let event = AppEvent::$y($e);
+            if policy.prevents(&event) {
+                $ee.prevent_default();
+            }
+            events.borrow_mut().push(event);
+        }
+    }};
+
     ($events:expr, $x:ident, $y:ident, $ee:ident, $e:expr, $prevent:expr) => {{
         let events = $events.clone();
         move |$ee: $x| {
@@ -42,41 +125,194 @@ macro_rules! map_event {
             events.borrow_mut().push(AppEvent::$y($e));
         }
     }};
+
+    ($events:expr, $x:ident, $y:ident) => {{
+        let events = $events.clone();
+        move |_: $x| {
+            events.borrow_mut().push(AppEvent::$y);
+        }
+    }};
+}
+
+macro_rules! map_touch_event {
+    ($events:expr, $emulation:expr, $canvas:expr, $x:ident, $y:ident) => {{
+        let events = $events.clone();
+        let emulation = $emulation.clone();
+        let canvas = $canvas.clone();
+        move |e: $x| {
+            // also prevents the browser from generating mouse events
+            e.prevent_default();
+            let mut events = events.borrow_mut();
+            for touch in e.changed_touches() {
+                let evt = AppEvent::$y(events::TouchEvent {
+                    id: touch.identifier() as u64,
+                    pos: canvas_position(&canvas, (touch.client_x(), touch.client_y())),
+                });
+                if let Some(ref emulation) = emulation {
+                    emulation.borrow_mut().translate(&evt, &mut events);
+                }
+                events.push(evt);
+            }
+        }
+    }};
+}
+
+// convert a position in CSS pixels from the browser viewport top-left to physical pixels
+// from the canvas top-left. The canvas rect is read every time because the page can scroll
+fn canvas_position(canvas: &CanvasElement, client_pos: (f64, f64)) -> (f64, f64) {
+    let (left, top) = canvas_offset(canvas);
+    let ratio = device_pixel_ratio();
+    ((client_pos.0 - left) * ratio, (client_pos.1 - top) * ratio)
+}
+
+// the current ratio between physical and CSS pixels. Changes with the browser zoom
+fn device_pixel_ratio() -> f64 {
+    js! { return window.devicePixelRatio; }.try_into().unwrap()
+}
+
+// position of the canvas top-left corner in the browser viewport
+fn canvas_offset(canvas: &CanvasElement) -> (f64, f64) {
+    let left: f64 = js! { return @{canvas}.getBoundingClientRect().left; }
+        .try_into()
+        .unwrap();
+    let top: f64 = js! { return @{canvas}.getBoundingClientRect().top; }
+        .try_into()
+        .unwrap();
+    (left, top)
+}
+
+fn css_cursor(icon: CursorIcon) -> &'static str {
+    match icon {
+        CursorIcon::Default => "default",
+        CursorIcon::Text => "text",
+        CursorIcon::Hand => "pointer",
+        CursorIcon::Crosshair => "crosshair",
+        CursorIcon::Wait => "wait",
+        CursorIcon::Progress => "progress",
+        CursorIcon::Help => "help",
+        CursorIcon::Move => "move",
+        CursorIcon::NotAllowed => "not-allowed",
+        CursorIcon::Grab => "grab",
+        CursorIcon::Grabbing => "grabbing",
+        CursorIcon::EResize => "e-resize",
+        CursorIcon::NResize => "n-resize",
+        CursorIcon::NeResize => "ne-resize",
+        CursorIcon::NwResize => "nw-resize",
+        CursorIcon::SResize => "s-resize",
+        CursorIcon::SeResize => "se-resize",
+        CursorIcon::SwResize => "sw-resize",
+        CursorIcon::WResize => "w-resize",
+        CursorIcon::EwResize => "ew-resize",
+        CursorIcon::NsResize => "ns-resize",
+        CursorIcon::NeswResize => "nesw-resize",
+        CursorIcon::NwseResize => "nwse-resize",
+    }
 }
 
-// In browser request full screen can only called under event handler.
-// So basically this function is useless at this moment.
-#[allow(dead_code)]
-fn request_full_screen(canvas: &CanvasElement) {
+fn key_down_event(e: &KeyDownEvent) -> events::KeyDownEvent {
+    events::KeyDownEvent {
+        code: e.code(),
+        key_code: e.code().parse().unwrap_or(KeyCode::Unidentified(0)),
+        key: e.key(),
+        shift: e.shift_key(),
+        alt: e.alt_key(),
+        ctrl: e.ctrl_key(),
+        logo: e.meta_key(),
+        repeat: e.repeat(),
+    }
+}
+
+fn key_up_event(e: &KeyUpEvent) -> events::KeyUpEvent {
+    events::KeyUpEvent {
+        code: e.code(),
+        key_code: e.code().parse().unwrap_or(KeyCode::Unidentified(0)),
+        key: e.key(),
+        shift: e.shift_key(),
+        alt: e.alt_key(),
+        ctrl: e.ctrl_key(),
+        logo: e.meta_key(),
+        repeat: e.repeat(),
+    }
+}
+
+// focus moving between the canvas and the hidden text input element is not reported
+fn focus_listener<T: IFocusEvent + ConcreteEvent>(
+    events: &Rc<RefCell<Vec<AppEvent>>>,
+    other: &Reference,
+    event: AppEvent,
+) -> impl FnMut(T) + 'static {
+    let events = events.clone();
+    let other = other.clone();
+    move |e: T| {
+        if e.related_target().as_ref().map(AsRef::as_ref) != Some(&other) {
+            events.borrow_mut().push(event.clone());
+        }
+    }
+}
+
+// convert a position in UTF-16 code units (javascript strings) to a position in characters
+fn utf16_to_char_index(text: &str, offset: usize) -> usize {
+    let mut units = 0;
+    text.chars()
+        .take_while(|c| {
+            units += c.len_utf16();
+            units <= offset
+        })
+        .count()
+}
+
+fn is_fullscreen(canvas: &CanvasElement) -> bool {
     js! {
-        var c = @{&canvas};
-        if (c.requestFullscreen) {
-            c.requestFullscreen();
-        } else if (c.webkitRequestFullscreen) {
-            c.webkitRequestFullscreen(Element.ALLOW_KEYBOARD_INPUT);
-        } else if (c.mozRequestFullScreen) {
-            c.mozRequestFullScreen();
-        } else if (c.msRequestFullscreen) {
-            c.msRequestFullscreen();
-        }
-    };
+        var element = document.fullscreenElement || document.webkitFullscreenElement;
+        return element === @{canvas};
+    }
+    .try_into()
+    .unwrap()
+}
+
+// an existing canvas, by element id or CSS selector
+fn find_canvas(selector: &str) -> Option<CanvasElement> {
+    use stdweb::web::{INonElementParentNode, IParentNode};
+
+    document()
+        .get_element_by_id(selector)
+        .or_else(|| document().query_selector(selector).ok().flatten())
+        .and_then(|element| element.try_into().ok())
+}
+
+fn is_target<T: IEvent>(e: &T, element: &HtmlElement) -> bool {
+    e.target()
+        .is_some_and(|target| target.as_ref() == element.as_ref())
+}
+
+fn is_paste_shortcut(e: &KeyDownEvent) -> bool {
+    // shift and caps lock give "V"
+    (e.ctrl_key() || e.meta_key()) && e.key().eq_ignore_ascii_case("v")
 }
 
 impl App {
     pub fn new(config: AppConfig) -> App {
         use stdweb::web::*;
 
-        if config.headless {
-            // Right now we did not support headless in web.
-            unimplemented!();
-        }
+        // headless and null backends use a canvas that is not attached to the page and receives no input
+        let detached = config.headless || config.null_backend || cfg!(feature = "null-backend");
 
         let _ = stdweb::initialize();
-        let canvas: CanvasElement = document()
-            .create_element("canvas")
-            .unwrap()
-            .try_into()
-            .unwrap();
+        let existing = config.canvas.as_ref().and_then(|selector| {
+            let canvas = find_canvas(selector);
+            if canvas.is_none() {
+                App::print(format!("Could not find the canvas {}\n", selector));
+            }
+            canvas
+        });
+        let attached = existing.is_some();
+        let canvas: CanvasElement = existing.unwrap_or_else(|| {
+            document()
+                .create_element("canvas")
+                .unwrap()
+                .try_into()
+                .unwrap()
+        });
 
         js! {
             // setup the buffer size
@@ -91,46 +327,140 @@ impl App {
 
             // Make it focusable
             // https://stackoverflow.com/questions/12886286/addeventlistener-for-keydown-on-canvas
-            @{&canvas}.tabIndex = 1;
-
+            if (@{&canvas}.tabIndex < 0) {
+                @{&canvas}.tabIndex = 1;
+            }
 
-            document.body.addEventListener("dragover", e => {e.prevent_default(); return false;});
-            document.body.addEventListener("dragenter", e => {e.prevent_default(); return false;});
-            document.body.addEventListener("drop", e => {e.prevent_default(); return false;});
+            // keep the browser from opening the dropped files
+            document.body.addEventListener("dragover", e => {e.preventDefault(); return false;});
+            document.body.addEventListener("dragenter", e => {e.preventDefault(); return false;});
+            document.body.addEventListener("drop", e => {e.preventDefault(); return false;});
         };
 
-        if !config.show_cursor {
-            js! {
-                @{&canvas}.style.cursor="none";
-            };
-        }
-
         let device_pixel_ratio: f64 = js! { return window.devicePixelRatio; }.try_into().unwrap();
 
-        let body = document().query_selector("body").unwrap().unwrap();
-
-        body.append_child(&canvas);
+        let text_input: HtmlElement = document()
+            .create_element("input")
+            .unwrap()
+            .try_into()
+            .unwrap();
         js! {
-            @{&canvas}.focus();
+            var input = @{&text_input};
+            input.type = "text";
+            input.autocomplete = "off";
+            // invisible, but still focusable. The browser shows the input method
+            // candidate window next to it, see set_ime_position
+            input.style.position = "fixed";
+            input.style.left = "0px";
+            input.style.top = "0px";
+            input.style.width = "1px";
+            input.style.height = "1px";
+            input.style.opacity = "0";
+            input.style.pointerEvents = "none";
         }
 
-        if config.fullscreen {
-            println!("Webgl do not support with_screen.");
+        if !detached {
+            let body = document().query_selector("body").unwrap().unwrap();
+
+            if !attached {
+                body.append_child(&canvas);
+            }
+            body.append_child(&text_input);
+            js! {
+                @{&canvas}.focus();
+            }
         }
 
+        set_exit_requested(false);
+        let params = App::get_params();
         let mut app = App {
             window: canvas,
             events: Rc::new(RefCell::new(Vec::new())),
             device_pixel_ratio: device_pixel_ratio as f32,
-            dropped_files: Rc::new(RefCell::new(Vec::new())),
+            sizing: if detached {
+                None
+            } else {
+                Some(config.canvas_sizing)
+            },
+            size: config.size,
+            dropped_files: Rc::new(RefCell::new(VecDeque::new())),
+            touch_emulation: if config.touch_to_mouse {
+                Some(Rc::new(RefCell::new(TouchMouseEmulation::default())))
+            } else {
+                None
+            },
+            gamepads: GamepadManager::new(),
+            input: InputState::default(),
+            gesture_requests: Rc::new(RefCell::new(Vec::new())),
+            cursor: "default".to_owned(),
+            cursor_visible: config.show_cursor,
+            text_input,
+            keyboard_layout: Rc::new(RefCell::new(HashMap::new())),
+            prevent_default: Rc::new(config.prevent_default),
+            listen_on_document: config.listen_on_document,
+            injected: Vec::new(),
+            recorder: config
+                .record
+                .or_else(|| get_param(&params, "record"))
+                .map(|path| Recorder::new(&path, now())),
+            replay: config
+                .replay
+                .or_else(|| get_param(&params, "replay"))
+                .and_then(|path| match Replay::open(&path) {
+                    Ok(replay) => Some(replay),
+                    Err(e) => {
+                        App::print(format!("Could not open the recording {} : {}\n", path, e));
+                        None
+                    }
+                }),
         };
-        app.setup_listener();
+        app.update_cursor();
+        app.query_keyboard_layout();
+        if !detached {
+            app.setup_listener();
+            app.update_canvas_size();
+            if config.fullscreen || config.window_mode.is_fullscreen() {
+                app.set_fullscreen(true);
+            }
+        }
 
         app
     }
 
+    // navigator.keyboard is only available in chromium based browsers.
+    // Elsewhere, the layout is learned from the key events
+    fn query_keyboard_layout(&self) {
+        let on_key = {
+            let layout = self.keyboard_layout.clone();
+            move |code: String, key: String| {
+                layout.borrow_mut().insert(code, key);
+            }
+        };
+        js! {
+            var on_key = @{on_key};
+            if (navigator.keyboard && navigator.keyboard.getLayoutMap) {
+                navigator.keyboard.getLayoutMap().then(function(map) {
+                    map.forEach(function(key, code) {
+                        on_key(code, key);
+                    });
+                    on_key.drop();
+                }, function() {
+                    on_key.drop();
+                });
+            } else {
+                on_key.drop();
+            }
+        }
+    }
+
     fn setup_listener(&mut self) {
         let canvas: &CanvasElement = self.canvas();
+        let policy = &self.prevent_default;
+        let key_target: EventTarget = if self.listen_on_document {
+            document().into()
+        } else {
+            canvas.clone().into()
+        };
 
         canvas.add_event_listener(map_event! {
             self.events,
@@ -148,6 +478,7 @@ impl App {
         });
         canvas.add_event_listener(map_event! {
             self.events,
+            policy,
             MouseUpEvent,
             MouseUp,
             e,
@@ -157,83 +488,254 @@ impl App {
                 MouseButton::Right => 2,
                 MouseButton::Button4 => 3,
                 MouseButton::Button5 => 4,
-            }},
-            true
+            }}
+        });
+
+        canvas.add_event_listener({
+            let events = self.events.clone();
+            let policy = policy.clone();
+            move |e: MouseWheelEvent| {
+                // browsers report a positive deltaY when scrolling down, native does the opposite
+                let (dx, dy) = (e.delta_x(), -e.delta_y());
+                let event = match e.delta_mode() {
+                    MouseWheelDeltaMode::Pixel => {
+                        let ratio: f64 =
+                            js! { return window.devicePixelRatio; }.try_into().unwrap();
+                        events::MouseWheelEvent {
+                            pixel_x: dx * ratio,
+                            pixel_y: dy * ratio,
+                            ..Default::default()
+                        }
+                    }
+                    // page scrolling is reported as lines
+                    _ => events::MouseWheelEvent {
+                        line_x: dx as f32,
+                        line_y: dy as f32,
+                        ..Default::default()
+                    },
+                };
+                let event = AppEvent::MouseWheel(event);
+                if policy.prevents(&event) {
+                    e.prevent_default();
+                }
+                events.borrow_mut().push(event);
+            }
         });
 
         canvas.add_event_listener({
             let canvas = canvas.clone();
-            let canvas_x: f64 = js! {
-            return @{&canvas}.getBoundingClientRect().left; }
-            .try_into()
-            .unwrap();
-            let canvas_y: f64 = js! {
-            return @{&canvas}.getBoundingClientRect().top; }
-            .try_into()
-            .unwrap();
             map_event! {
                 self.events,
+                policy,
                 MouseMoveEvent,
                 MousePos,
                 e,
-                (e.client_x() as f64 - canvas_x,e.client_y() as f64 - canvas_y),
-                true
+                canvas_position(&canvas, (f64::from(e.client_x()), f64::from(e.client_y())))
             }
         });
 
         canvas.add_event_listener(map_event! {
             self.events,
-            KeyDownEvent,
-            KeyDown,
+            MouseMoveEvent,
+            MouseMotion,
             e,
-            events::KeyDownEvent {
-                code: e.code(),
-                key: e.key(),
-                shift: e.shift_key(),
-                alt: e.alt_key(),
-                ctrl: e.ctrl_key(),
+            {
+                // movement is in CSS pixels
+                let ratio = device_pixel_ratio();
+                events::MouseMotionEvent {
+                    dx: e.movement_x() as f64 * ratio,
+                    dy: e.movement_y() as f64 * ratio,
+                }
             },
-            true
+            false
         });
 
-        canvas.add_event_listener({
+        document().add_event_listener({
+            let events = self.events.clone();
+            let canvas = canvas.clone();
+            move |_: PointerLockChangeEvent| {
+                let locked: bool = js! { return document.pointerLockElement === @{&canvas}; }
+                    .try_into()
+                    .unwrap();
+                events
+                    .borrow_mut()
+                    .push(AppEvent::PointerLockChanged(locked));
+            }
+        });
+
+        let on_fullscreen_change = {
+            let events = self.events.clone();
+            let canvas = canvas.clone();
+            move || {
+                events
+                    .borrow_mut()
+                    .push(AppEvent::FullscreenChanged(is_fullscreen(&canvas)));
+            }
+        };
+        // stdweb has no fullscreen events. Safari only has the prefixed event
+        js! {
+            var on_fullscreen_change = @{on_fullscreen_change};
+            var listener = function() {
+                on_fullscreen_change();
+            };
+            document.addEventListener("fullscreenchange", listener);
+            document.addEventListener("webkitfullscreenchange", listener);
+        }
+
+        canvas.add_event_listener(gesture_listener::<MouseDownEvent>(
+            canvas,
+            &self.gesture_requests,
+        ));
+        canvas.add_event_listener(gesture_listener::<MouseUpEvent>(
+            canvas,
+            &self.gesture_requests,
+        ));
+        key_target.add_event_listener(gesture_listener::<KeyDownEvent>(
+            canvas,
+            &self.gesture_requests,
+        ));
+        key_target.add_event_listener(gesture_listener::<KeyUpEvent>(
+            canvas,
+            &self.gesture_requests,
+        ));
+        canvas.add_event_listener(gesture_listener::<TouchEnd>(canvas, &self.gesture_requests));
+
+        canvas.add_event_listener(map_touch_event! {
+            self.events,
+            self.touch_emulation,
+            canvas,
+            TouchStart,
+            TouchStart
+        });
+        canvas.add_event_listener(map_touch_event! {
+            self.events,
+            self.touch_emulation,
+            canvas,
+            TouchMove,
+            TouchMove
+        });
+        canvas.add_event_listener(map_touch_event! {
+            self.events,
+            self.touch_emulation,
+            canvas,
+            TouchEnd,
+            TouchEnd
+        });
+        canvas.add_event_listener(map_touch_event! {
+            self.events,
+            self.touch_emulation,
+            canvas,
+            TouchCancel,
+            TouchCancel
+        });
+
+        // stdweb has no clipboard events
+        let on_paste = {
+            let events = self.events.clone();
+            move |text: String| {
+                events.borrow_mut().push(AppEvent::ClipboardText(text));
+            }
+        };
+        // same target as the keyboard listeners. Pastes in the text input are `TextCommit` events
+        js! {
+            var on_paste = @{on_paste};
+            var text_input = @{&self.text_input};
+            @{&key_target}.addEventListener("paste", function(e) {
+                if (e.target === text_input) {
+                    return;
+                }
+                e.preventDefault();
+                on_paste(e.clipboardData.getData("text"));
+            });
+        }
+
+        key_target.add_event_listener({
+            let events = self.events.clone();
+            let layout = self.keyboard_layout.clone();
+            let policy = policy.clone();
+            let text_input = self.text_input.clone();
+            move |e: KeyDownEvent| {
+                // already handled by the text input listeners
+                if is_target(&e, &text_input) {
+                    return;
+                }
+                let event = AppEvent::KeyDown(key_down_event(&e));
+                // the default action of the paste shortcut is the paste event
+                if policy.prevents(&event) && !is_paste_shortcut(&e) {
+                    e.prevent_default();
+                }
+                let mut events = events.borrow_mut();
+                events.push(event);
+                // control keys are named "Tab", "Backspace", ...
+                // ctrl+alt is AltGr on windows, used to type characters
+                let key = e.key();
+                if key.chars().count() == 1 && !e.meta_key() && (!e.ctrl_key() || e.alt_key()) {
+                    events.push(AppEvent::CharEvent(key.chars().next().unwrap()));
+                    if !e.shift_key() && !e.ctrl_key() && !e.alt_key() {
+                        layout.borrow_mut().insert(e.code(), key.to_lowercase());
+                    }
+                }
+            }
+        });
+        key_target.add_event_listener({
             let events = self.events.clone();
+            let policy = policy.clone();
+            let text_input = self.text_input.clone();
             move |e: KeyUpEvent| {
-                e.prevent_default();
-                // filter control keys "Tab", "Backspace", ...
-                if e.key().len() == 1 {
-                    events
-                        .borrow_mut()
-                        .push(AppEvent::CharEvent(e.key().chars().next().unwrap()));
+                if is_target(&e, &text_input) {
+                    return;
                 }
+                let event = AppEvent::KeyUp(key_up_event(&e));
+                if policy.prevents(&event) {
+                    e.prevent_default();
+                }
+                events.borrow_mut().push(event);
             }
         });
 
+        canvas.add_event_listener(focus_listener::<FocusEvent>(
+            &self.events,
+            self.text_input.as_ref(),
+            AppEvent::FocusGained,
+        ));
+        canvas.add_event_listener(focus_listener::<BlurEvent>(
+            &self.events,
+            self.text_input.as_ref(),
+            AppEvent::FocusLost,
+        ));
+
+        self.setup_text_input_listener();
+
         canvas.add_event_listener(map_event! {
             self.events,
-            KeyUpEvent,
-            KeyUp,
-            e,
-            events::KeyUpEvent {
-                code: e.code(),
-                key: e.key(),
-                shift: e.shift_key(),
-                alt: e.alt_key(),
-                ctrl: e.ctrl_key(),
-            },
-            true
+            MouseEnterEvent,
+            CursorEntered
+        });
+        canvas.add_event_listener(map_event! {
+            self.events,
+            MouseLeaveEvent,
+            CursorLeft
         });
 
         canvas.add_event_listener({
-            let canvas = canvas.clone();
-
-            map_event! {
-                self.events,
-                ResizeEvent,
-                Resized,
-                (canvas.offset_width() as u32, canvas.offset_height() as u32)
+            let events = self.events.clone();
+            move |e: DragEnterEvent| {
+                let has_files: bool = js! {
+                    var types = @{&e}.dataTransfer.types;
+                    return Array.prototype.indexOf.call(types, "Files") >= 0;
+                }
+                .try_into()
+                .unwrap();
+                if has_files {
+                    events.borrow_mut().push(AppEvent::FileHovered(None));
+                }
             }
         });
+        canvas.add_event_listener(map_event! {
+            self.events,
+            DragLeaveEvent,
+            FileHoverCancelled
+        });
 
         canvas.add_event_listener({
             let events = self.events.clone();
@@ -245,10 +747,7 @@ impl App {
                     let on_get_buffer = {
                         let buffer_state = buffer_state.clone();
                         move |ab: TypedArray<u8>| {
-                            let data = ab.to_vec();
-                            if data.len() > 0 {
-                                *buffer_state.borrow_mut() = BufferState::Buffer(data);
-                            }
+                            *buffer_state.borrow_mut() = BufferState::Buffer(ab.to_vec());
                         }
                     };
                     let on_error = {
@@ -258,51 +757,197 @@ impl App {
                             *buffer_state.borrow_mut() = BufferState::Error(msg);
                         }
                     };
-                    let name = f.name();
                     js! {
-                        var reader = new FileReader();
-                        var fname=@{name};
-                        var on_error_js = function(s){
-                            var on_error = @{on_error};
-                            on_error(s);
+                        var file = @{&f};
+                        var on_get_buffer = @{on_get_buffer};
+                        var on_error = @{on_error};
+                        var done = function() {
+                            on_get_buffer.drop();
                             on_error.drop();
                         };
+                        var reader = new FileReader();
                         reader.onload = function(e2) {
-                            var on_get_buffer = @{on_get_buffer};
                             on_get_buffer(new Uint8Array(e2.target.result));
-                            on_get_buffer.drop();
+                            done();
                         };
                         reader.onerror = function(e3) {
-                            var err_msg="Error while reading "+fname+" : "+e3;
+                            var err_msg="Error while reading "+file.name+" : "+reader.error;
                             console.log(err_msg);
-                            on_error_js(err_msg);
+                            on_error(err_msg);
+                            done();
                         };
                         reader.onabort = function(e4) {
-                            var err_msg="Reading of "+fname+" aborted : "+e4;
+                            var err_msg="Reading of "+file.name+" aborted";
                             console.log(err_msg);
-                            on_error_js(err_msg);
+                            on_error(err_msg);
+                            done();
                         };
+                        reader.readAsArrayBuffer(file);
                     }
                     events
                         .borrow_mut()
-                        .push(AppEvent::FileDropped(f.name().to_owned()));
-                    dropped_files.borrow_mut().push(File {
-                        buffer_state: buffer_state,
-                    });
+                        .push(AppEvent::FileDropped(events::FileDroppedEvent {
+                            name: f.name(),
+                            size: f.len(),
+                            mime_type: f.mime(),
+                            path: None,
+                        }));
+                    dropped_files.borrow_mut().push_back(File { buffer_state });
                 }
             }
         });
     }
 
+    // the hidden input element receives the keyboard events between start_text_input and stop_text_input
+    fn setup_text_input_listener(&self) {
+        let text_input = &self.text_input;
+        // keys used by the input method are not reported
+        text_input.add_event_listener({
+            let events = self.events.clone();
+            move |e: KeyDownEvent| {
+                if !e.is_composing() && e.key() != "Process" {
+                    events
+                        .borrow_mut()
+                        .push(AppEvent::KeyDown(key_down_event(&e)));
+                }
+            }
+        });
+        text_input.add_event_listener({
+            let events = self.events.clone();
+            move |e: KeyUpEvent| {
+                if !e.is_composing() && e.key() != "Process" {
+                    events.borrow_mut().push(AppEvent::KeyUp(key_up_event(&e)));
+                }
+            }
+        });
+        text_input.add_event_listener(focus_listener::<FocusEvent>(
+            &self.events,
+            self.window.as_ref(),
+            AppEvent::FocusGained,
+        ));
+        text_input.add_event_listener(focus_listener::<BlurEvent>(
+            &self.events,
+            self.window.as_ref(),
+            AppEvent::FocusLost,
+        ));
+
+        let on_composition =
+            {
+                let events = self.events.clone();
+                move |text: String, cursor: u32| {
+                    let cursor = utf16_to_char_index(&text, cursor as usize);
+                    events.borrow_mut().push(AppEvent::TextComposition(
+                        events::TextCompositionEvent { text, cursor },
+                    ));
+                }
+            };
+        let on_commit = {
+            let events = self.events.clone();
+            move |text: String| {
+                let text: String = text.chars().filter(|c| !c.is_control()).collect();
+                if !text.is_empty() {
+                    let mut events = events.borrow_mut();
+                    events.extend(text.chars().map(AppEvent::CharEvent));
+                    events.push(AppEvent::TextCommit(text));
+                }
+            }
+        };
+        // stdweb has no composition events
+        js! {
+            var input = @{text_input};
+            var on_composition = @{on_composition};
+            var on_commit = @{on_commit};
+            var composing = false;
+            input.addEventListener("compositionstart", function() {
+                composing = true;
+            });
+            input.addEventListener("input", function() {
+                if (composing) {
+                    on_composition(input.value, input.selectionStart || 0);
+                } else if (input.value.length > 0) {
+                    on_commit(input.value);
+                    input.value = "";
+                }
+            });
+            input.addEventListener("compositionend", function(e) {
+                composing = false;
+                on_composition("", 0);
+                if (e.data) {
+                    on_commit(e.data);
+                }
+                input.value = "";
+            });
+        }
+    }
+
+    /// start receiving `AppEvent::TextCommit` and `AppEvent::TextComposition` events,
+    /// for example when a text field gets the focus. The keyboard input goes to a hidden input element
+    /// so that the browser input methods work
+    pub fn start_text_input(&mut self) {
+        self.text_input.focus();
+    }
+
+    /// stop receiving `AppEvent::TextCommit` and `AppEvent::TextComposition` events
+    pub fn stop_text_input(&mut self) {
+        js! {
+            var input = @{&self.text_input};
+            input.value = "";
+            if (document.activeElement === input) {
+                @{&self.window}.focus();
+            }
+        }
+    }
+
+    /// position of the input method candidate window, in pixels from the canvas top-left.
+    /// Typically the text cursor position in the edited text field
+    pub fn set_ime_position(&self, pos: (f64, f64)) {
+        let (left, top) = canvas_offset(&self.window);
+        let ratio = f64::from(self.device_pixel_ratio);
+        js! {
+            var style = @{&self.text_input}.style;
+            style.left = (@{left} + @{pos.0} / @{ratio}) + "px";
+            style.top = (@{top} + @{pos.1} / @{ratio}) + "px";
+        }
+    }
+
+    /// return the next dropped file, in drop order (see `AppEvent::FileDropped`).
+    /// Wait for [`File::is_ready`] before reading it
     pub fn get_dropped_file(&mut self) -> Option<File> {
-        self.dropped_files.borrow_mut().pop()
+        self.dropped_files.borrow_mut().pop_front()
+    }
+
+    /// the character produced by a key with the current keyboard layout, without modifier.
+    /// For example `KeyCode::KeyW` gives "z" on an azerty keyboard.
+    /// Uses `navigator.keyboard.getLayoutMap` when the browser supports it.
+    /// Otherwise only the keys already pressed are known
+    pub fn key_label(&self, key_code: KeyCode) -> Option<String> {
+        self.keyboard_layout.borrow().get(key_code.name()).cloned()
+    }
+
+    /// the key producing a character with the current keyboard layout, see `key_label`
+    pub fn key_for_char(&self, c: char) -> Option<KeyCode> {
+        let label: String = c.to_lowercase().collect();
+        let layout = self.keyboard_layout.borrow();
+        KEY_CODES
+            .iter()
+            .cloned()
+            .find(|key_code| layout.get(key_code.name()) == Some(&label))
+    }
+
+    /// the native scancode of a W3C key code name. Browsers don't expose scancodes : always None
+    pub fn scan_code(_code: &str) -> Option<u32> {
+        None
     }
 
     pub fn print<T: Into<String>>(msg: T) {
         js! { console.log(@{msg.into()})};
     }
 
-    pub fn exit() {}
+    /// stop the animation frame loop at the end of the current frame and call `AppHandler::exit`.
+    /// This function returns immediately : the rest of the frame still runs
+    pub fn exit() {
+        set_exit_requested(true);
+    }
 
     pub fn get_screen_resolution(&self) -> (u32, u32) {
         (
@@ -320,25 +965,189 @@ impl App {
         return self.device_pixel_ratio;
     }
 
+    /// convert a position in physical pixels (as in the events) to CSS pixels
+    pub fn to_logical(&self, pos: (f64, f64)) -> (f64, f64) {
+        let factor = f64::from(self.device_pixel_ratio);
+        (pos.0 / factor, pos.1 / factor)
+    }
+
+    /// convert a position in CSS pixels to physical pixels, see `to_logical`
+    pub fn to_physical(&self, pos: (f64, f64)) -> (f64, f64) {
+        let factor = f64::from(self.device_pixel_ratio);
+        (pos.0 * factor, pos.1 * factor)
+    }
+
+    /// the canvas of the game. With the null and headless backends, it is not attached to the page
     pub fn canvas(&self) -> &CanvasElement {
         &self.window
     }
 
-    pub fn run_loop<F>(mut self, mut callback: F)
+    pub fn gamepads<'a>(&'a self) -> Box<dyn Iterator<Item = &'a GamepadState> + 'a> {
+        self.gamepads.gamepads()
+    }
+
+    pub fn gamepad(&self, id: usize) -> Option<&GamepadState> {
+        self.gamepads().find(|g| g.id == id)
+    }
+
+    pub fn input(&self) -> &InputState {
+        &self.input
+    }
+
+    // the fill modes set the CSS size from the page layout, then the backing store follows the CSS size.
+    // Browsers have no resize event for elements, so this is checked every frame
+    fn update_canvas_size(&mut self) {
+        let sizing = match self.sizing {
+            Some(sizing) => sizing,
+            None => return,
+        };
+        let canvas = &self.window;
+        let area: Option<Vec<f64>> = match sizing {
+            CanvasSizing::Fixed => None,
+            CanvasSizing::FillWindow => {
+                js! {
+                    var style = @{canvas}.style;
+                    style.position = "fixed";
+                    style.left = "0px";
+                    style.top = "0px";
+                };
+                js! { return [window.innerWidth, window.innerHeight]; }
+                    .try_into()
+                    .ok()
+            }
+            CanvasSizing::FillParent | CanvasSizing::KeepAspectRatio => js! {
+                var parent = @{canvas}.parentElement;
+                return parent ? [parent.clientWidth, parent.clientHeight] : [0, 0];
+            }
+            .try_into()
+            .ok(),
+        };
+        if let Some(area) = area {
+            let (mut width, mut height) = (area[0], area[1]);
+            if sizing == CanvasSizing::KeepAspectRatio {
+                let aspect_ratio = f64::from(self.size.0) / f64::from(self.size.1);
+                if width > height * aspect_ratio {
+                    width = height * aspect_ratio;
+                } else {
+                    height = width / aspect_ratio;
+                }
+            }
+            js! {
+                var style = @{canvas}.style;
+                // an inline canvas adds a line below it, growing its parent
+                style.display = "block";
+                style.width = @{width} + "px";
+                style.height = @{height} + "px";
+            }
+        }
+        let ratio = f64::from(self.device_pixel_ratio);
+        let css_size: Vec<f64> = js! {
+            var rect = @{canvas}.getBoundingClientRect();
+            return [rect.width, rect.height];
+        }
+        .try_into()
+        .unwrap();
+        let size = (
+            (css_size[0] * ratio).round() as u32,
+            (css_size[1] * ratio).round() as u32,
+        );
+        if size.0 > 0 && size.1 > 0 && size != self.inner_size() {
+            canvas.set_width(size.0);
+            canvas.set_height(size.1);
+            self.events.borrow_mut().push(AppEvent::Resized(size));
+        }
+    }
+
+    // update the gamepads and input state before calling the frame callback
+    fn begin_frame(&mut self) {
+        // the browser has no event for this, it changes when the page is zoomed
+        // or the window moved to another monitor
+        let device_pixel_ratio: f64 = js! { return window.devicePixelRatio; }.try_into().unwrap();
+        if device_pixel_ratio as f32 != self.device_pixel_ratio {
+            self.device_pixel_ratio = device_pixel_ratio as f32;
+            self.events
+                .borrow_mut()
+                .push(AppEvent::ScaleFactorChanged(self.device_pixel_ratio));
+        }
+        self.update_canvas_size();
+        if let Some(ref mut replay) = self.replay {
+            let mut events = self.events.borrow_mut();
+            // the real input is ignored while replaying
+            events.clear();
+            if !replay.next_frame(&mut events) {
+                App::print("Replay finished\n");
+                self.replay = None;
+            }
+        } else {
+            self.gamepads.poll(&mut self.events.borrow_mut());
+        }
+        self.end_frame_events();
+    }
+
+    // add the injected events to the frame events, then record them and update the input state
+    fn end_frame_events(&mut self) {
+        self.events.borrow_mut().append(&mut self.injected);
+        if let Some(ref mut recorder) = self.recorder {
+            recorder.record(now(), &self.events.borrow());
+        }
+        self.input.update(&self.events.borrow());
+    }
+
+    pub fn run_loop<F>(self, callback: F)
     where
         F: 'static + FnMut(&mut Self) -> (),
     {
-        window().request_animation_frame(move |_t: f64| {
-            callback(&mut self);
-            self.events.borrow_mut().clear();
-            self.run_loop(callback);
-        });
+        self.start_handler(FnHandler(callback));
+    }
+
+    // start the request_animation_frame loop
+    fn start_handler<H: 'static + AppHandler>(mut self, mut handler: H) {
+        handler.init(&mut self);
+        let runner = Rc::new(RefCell::new(Runner {
+            last_time: now(),
+            app: self,
+            handler,
+            exited: false,
+        }));
+        let on_page_hide = {
+            let runner = runner.clone();
+            move || runner.borrow_mut().exit()
+        };
+        // stdweb has no pagehide event. unload is not reliable on mobile browsers
+        js! {
+            var on_page_hide = @{on_page_hide};
+            window.addEventListener("pagehide", function() {
+                on_page_hide();
+            });
+        }
+        frame_loop(runner);
+    }
+
+    /// add a synthetic event. It is delivered in `App.events` on next frame,
+    /// after the real input events and in injection order
+    pub fn inject_event(&mut self, event: AppEvent) {
+        self.injected.push(event);
+    }
+
+    /// run a single frame for automated tests : the frame events are only the ones added with
+    /// `inject_event`. The browser events wait for the next `poll_events`
+    pub fn step<F>(&mut self, callback: F) -> bool
+    where
+        F: FnOnce(&mut Self),
+    {
+        let browser_events = self.events.replace(Vec::new());
+        self.end_frame_events();
+        callback(self);
+        *self.events.borrow_mut() = browser_events;
+
+        true
     }
 
     pub fn poll_events<F>(&mut self, callback: F) -> bool
     where
         F: FnOnce(&mut Self) -> (),
     {
+        self.begin_frame();
         callback(self);
         self.events.borrow_mut().clear();
 
@@ -349,17 +1158,280 @@ impl App {
     where
         F: 'static + FnMut(&mut Self) -> (),
     {
-        self.run_loop(callback);
+        self.run_handler(FnHandler(callback));
+    }
+
+    /// start the game loop, calling the handler methods every frame
+    pub fn run_handler<H: 'static + AppHandler>(self, handler: H) {
+        self.start_handler(handler);
 
         stdweb::event_loop();
     }
 
-    pub fn set_fullscreen(&mut self, _b: bool) {
-        // unimplemented!();
+    /// confine the mouse cursor to the canvas, for first-person controls.
+    /// Use `AppEvent::MouseMotion` to get the mouse movements while the cursor is grabbed.
+    /// The browser only allows it during a user input, so the request is delayed until the next
+    /// mouse button or key event on the canvas. `AppEvent::PointerLockChanged` is sent once it is done.
+    pub fn set_cursor_grab(&self, grab: bool) {
+        let mut requests = self.gesture_requests.borrow_mut();
+        requests.retain(|r| *r != GestureRequest::PointerLock);
+        if grab {
+            requests.push(GestureRequest::PointerLock);
+        } else {
+            document().exit_pointer_lock();
+        }
+    }
+
+    /// change the mouse cursor shape
+    pub fn set_cursor(&mut self, icon: CursorIcon) {
+        self.cursor = css_cursor(icon).to_owned();
+        self.update_cursor();
+    }
+
+    /// use an image as mouse cursor. `rgba` contains `width` x `height` pixels, 4 bytes per pixel,
+    /// else the image is ignored.
+    /// `hotspot` is the position of the click point in the image
+    pub fn set_custom_cursor(&mut self, rgba: &[u8], width: u32, height: u32, hotspot: (u32, u32)) {
+        if let Err(msg) = check_cursor_image(rgba, width, height) {
+            App::print(format!("Could not set the cursor : {}\n", msg));
+            return;
+        }
+        let url: String = js! {
+            var canvas = document.createElement("canvas");
+            canvas.width = @{width};
+            canvas.height = @{height};
+            var ctx = canvas.getContext("2d");
+            var image = ctx.createImageData(@{width}, @{height});
+            image.data.set(@{TypedArray::<u8>::from(rgba)});
+            ctx.putImageData(image, 0, 0);
+            return canvas.toDataURL();
+        }
+        .try_into()
+        .unwrap();
+        self.cursor = format!("url({}) {} {}, auto", url, hotspot.0, hotspot.1);
+        self.update_cursor();
+    }
+
+    /// show or hide the mouse cursor while it is over the canvas
+    pub fn set_cursor_visible(&mut self, visible: bool) {
+        self.cursor_visible = visible;
+        self.update_cursor();
+    }
+
+    fn update_cursor(&self) {
+        let cursor = if self.cursor_visible {
+            &self.cursor[..]
+        } else {
+            "none"
+        };
+        js! {
+            @{&self.window}.style.cursor = @{cursor};
+        }
+    }
+
+    /// copy a text to the system clipboard
+    pub fn set_clipboard_text(&self, text: &str) {
+        js! {
+            if (navigator.clipboard && navigator.clipboard.writeText) {
+                navigator.clipboard.writeText(@{text}).catch(function(e) {
+                    console.log("Could not write the clipboard : " + e);
+                });
+            }
+        }
+    }
+
+    /// ask for the system clipboard content. It is received later in an `AppEvent::ClipboardText` event.
+    /// Some browsers don't allow it, in that case the text is only received when the user pastes it (ctrl-v)
+    pub fn request_clipboard_text(&self) {
+        let on_text = {
+            let events = self.events.clone();
+            move |text: String| {
+                events.borrow_mut().push(AppEvent::ClipboardText(text));
+            }
+        };
+        js! {
+            var on_text = @{stdweb::Once(on_text)};
+            if (navigator.clipboard && navigator.clipboard.readText) {
+                navigator.clipboard.readText().then(on_text, function(e) {
+                    console.log("Could not read the clipboard : " + e);
+                    on_text.drop();
+                });
+            } else {
+                on_text.drop();
+            }
+        }
+    }
+
+    /// activate or deactivate fullscreen. Browsers only allow fullscreen from a user input handler :
+    /// the canvas goes fullscreen on the next key press or mouse click.
+    /// An `AppEvent::FullscreenChanged` event is sent when it happens
+    pub fn set_fullscreen(&self, b: bool) {
+        let mut requests = self.gesture_requests.borrow_mut();
+        requests.retain(|r| *r != GestureRequest::Fullscreen);
+        if b {
+            if !self.is_fullscreen() {
+                requests.push(GestureRequest::Fullscreen);
+            }
+        } else if self.is_fullscreen() {
+            js! {
+                if (document.exitFullscreen) {
+                    document.exitFullscreen();
+                } else if (document.webkitExitFullscreen) {
+                    document.webkitExitFullscreen();
+                }
+            }
+        }
+    }
+
+    /// whether the canvas is fullscreen
+    pub fn is_fullscreen(&self) -> bool {
+        is_fullscreen(&self.window)
+    }
+
+    /// switch between windowed and fullscreen. The monitor is ignored
+    pub fn set_window_mode(&self, mode: WindowMode) {
+        self.set_fullscreen(mode.is_fullscreen());
     }
+
+    /// the screen displaying the page
+    pub fn monitors(&self) -> Vec<Monitor> {
+        let ratio = f64::from(self.device_pixel_ratio);
+        let width: f64 = js! { return screen.width; }.try_into().unwrap();
+        let height: f64 = js! { return screen.height; }.try_into().unwrap();
+        let size = ((width * ratio) as u32, (height * ratio) as u32);
+        vec![Monitor {
+            name: None,
+            position: (0, 0),
+            size,
+            scale_factor: self.device_pixel_ratio,
+        }]
+    }
+
+    /// index in `monitors` of the monitor containing the page : always 0
+    pub fn current_monitor(&self) -> Option<usize> {
+        Some(0)
+    }
+
+    /// change the page title
+    pub fn set_title(&self, title: &str) {
+        document().set_title(title);
+    }
+
+    /// size of the canvas backing store in pixels
+    pub fn inner_size(&self) -> (u32, u32) {
+        (self.window.width(), self.window.height())
+    }
+
+    /// resize the canvas, in pixels. The CSS size is the size divided by `hidpi_factor`.
+    /// An `AppEvent::Resized` event is sent. Only lasts until the next frame when
+    /// `AppConfig.canvas_sizing` is not `Fixed`
+    pub fn set_inner_size(&self, size: (u32, u32)) {
+        self.window.set_width(size.0);
+        self.window.set_height(size.1);
+        let ratio = f64::from(self.device_pixel_ratio);
+        js! {
+            var style = @{&self.window}.style;
+            style.width = (@{size.0} / @{ratio}) + "px";
+            style.height = (@{size.1} / @{ratio}) + "px";
+        }
+        self.events.borrow_mut().push(AppEvent::Resized(size));
+    }
+
+    /// the page position is not available : always None
+    pub fn outer_position(&self) -> Option<(i32, i32)> {
+        None
+    }
+
+    /// browsers can't move the window : does nothing
+    pub fn set_outer_position(&self, _pos: (i32, i32)) {}
+
+    /// minimum CSS size of the canvas, in pixels. None removes the constraint
+    pub fn set_min_size(&self, size: Option<(u32, u32)>) {
+        let (width, height) = self.css_size(size);
+        js! {
+            var style = @{&self.window}.style;
+            style.minWidth = @{width};
+            style.minHeight = @{height};
+        }
+    }
+
+    /// maximum CSS size of the canvas, in pixels. None removes the constraint
+    pub fn set_max_size(&self, size: Option<(u32, u32)>) {
+        let (width, height) = self.css_size(size);
+        js! {
+            var style = @{&self.window}.style;
+            style.maxWidth = @{width};
+            style.maxHeight = @{height};
+        }
+    }
+
+    // CSS lengths of a size in pixels. Empty strings to remove the property
+    fn css_size(&self, size: Option<(u32, u32)>) -> (String, String) {
+        let ratio = f64::from(self.device_pixel_ratio);
+        match size {
+            Some((width, height)) => (
+                format!("{}px", f64::from(width) / ratio),
+                format!("{}px", f64::from(height) / ratio),
+            ),
+            None => (String::new(), String::new()),
+        }
+    }
+
+    /// the user can't resize the canvas : does nothing
+    pub fn set_resizable(&self, _resizable: bool) {}
+
+    /// show or hide the canvas. A hidden canvas keeps its place in the page
+    pub fn set_visible(&self, visible: bool) {
+        js! {
+            @{&self.window}.style.visibility = @{visible} ? "" : "hidden";
+        }
+    }
+}
+
+/// the state of the animation frame loop
+struct Runner<H: AppHandler> {
+    app: App,
+    handler: H,
+    last_time: f64,
+    /// whether the handler exit has been called
+    exited: bool,
+}
+
+impl<H: AppHandler> Runner<H> {
+    fn exit(&mut self) {
+        if !self.exited {
+            self.exited = true;
+            self.handler.exit(&mut self.app);
+        }
+        if let Some(ref mut recorder) = self.app.recorder {
+            recorder.flush();
+        }
+    }
+}
+
+fn frame_loop<H: 'static + AppHandler>(runner: Rc<RefCell<Runner<H>>>) {
+    window().request_animation_frame(move |_t: f64| {
+        {
+            let runner = &mut *runner.borrow_mut();
+            let app = &mut runner.app;
+            app.begin_frame();
+            let time = now();
+            handle_frame(app, &mut runner.handler, time - runner.last_time);
+            runner.last_time = time;
+            app.events.borrow_mut().clear();
+            if exit_requested() {
+                runner.exit();
+                return;
+            }
+        }
+        frame_loop(runner);
+    });
 }
 
 pub fn now() -> f64 {
+    if let Some(t) = replay_time() {
+        return t;
+    }
     // perforamce now is in ms
     // https://developer.mozilla.org/en-US/docs/Web/API/Performance/now
     let v = js! { return performance.now() / 1000.0; };
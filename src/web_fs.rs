@@ -1,8 +1,8 @@
 use std;
 use std::cell::RefCell;
-use std::io::ErrorKind;
 use std::rc::Rc;
 use std::str;
+use stdweb::unstable::TryInto;
 use stdweb::web::TypedArray;
 
 pub type IoError = std::io::Error;
@@ -20,7 +20,15 @@ pub struct File {
 }
 
 impl FileSystem {
+    /// a file written with `FileSystem::write` is read from the local storage, even if the server
+    /// has a file with the same path
     pub fn open(s: &str) -> Result<File, IoError> {
+        if let Some(data) = read_local_storage(s) {
+            return Ok(File {
+                buffer_state: Rc::new(RefCell::new(BufferState::Buffer(data))),
+            });
+        }
+
         let buffer_state = Rc::new(RefCell::new(BufferState::Empty));
 
         let on_get_buffer = {
@@ -76,6 +84,43 @@ impl FileSystem {
             buffer_state: buffer_state,
         })
     }
+
+    pub fn write(s: &str, data: &[u8]) -> Result<(), IoError> {
+        // local storage only stores strings. store one byte per character
+        let content: String = data.iter().map(|b| *b as char).collect();
+        let stored: bool = js! {
+            try {
+                window.localStorage.setItem(@{s}, @{content});
+                return true;
+            } catch(e) {
+                console.log("Fail to write " + @{s} + " : " + e);
+                return false;
+            }
+        }
+        .try_into()
+        .unwrap();
+        if stored {
+            Ok(())
+        } else {
+            Err(std::io::Error::other(format!(
+                "Fail to write {} in local storage",
+                s
+            )))
+        }
+    }
+}
+
+fn read_local_storage(s: &str) -> Option<Vec<u8>> {
+    let content: Option<String> = js! {
+        try {
+            return window.localStorage.getItem(@{s});
+        } catch(e) {
+            return null;
+        }
+    }
+    .try_into()
+    .unwrap();
+    content.map(|c| c.chars().map(|c| c as u8).collect())
 }
 
 impl File {
@@ -91,7 +136,7 @@ impl File {
     pub fn read_binary(&mut self) -> Result<Vec<u8>, IoError> {
         let mut bs = self.buffer_state.borrow_mut();
         match *bs {
-            BufferState::Error(ref s) => Err(std::io::Error::new(ErrorKind::Other, s.clone())),
+            BufferState::Error(ref s) => Err(std::io::Error::other(s.clone())),
             BufferState::Buffer(ref mut v) => Ok({
                 let mut r = Vec::new();
                 r.append(v);
@@ -104,9 +149,9 @@ impl File {
     pub fn read_text(&mut self) -> Result<String, IoError> {
         let mut bs = self.buffer_state.borrow_mut();
         match *bs {
-            BufferState::Error(ref s) => Err(std::io::Error::new(ErrorKind::Other, s.clone())),
+            BufferState::Error(ref s) => Err(std::io::Error::other(s.clone())),
             BufferState::Buffer(ref mut v) => match str::from_utf8(v) {
-                Err(e) => Err(std::io::Error::new(ErrorKind::Other, e)),
+                Err(e) => Err(std::io::Error::other(e)),
                 Ok(v) => Ok(v.to_owned()),
             },
             _ => unreachable!(),
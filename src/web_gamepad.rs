@@ -0,0 +1,63 @@
+use stdweb::web::{Gamepad, GamepadMappingType};
+
+use AppEvent;
+use {GamepadAxis, GamepadButton, GamepadState};
+
+/// keeps track of the connected gamepads using the browser Gamepad API
+pub struct GamepadManager {
+    gamepads: Vec<Option<GamepadState>>,
+}
+
+impl GamepadManager {
+    pub fn new() -> GamepadManager {
+        GamepadManager {
+            gamepads: Vec::new(),
+        }
+    }
+
+    /// read the gamepads state and push the gamepad events.
+    /// The browser doesn't send events on button/axis changes, so this has to be called every frame.
+    pub fn poll(&mut self, events: &mut Vec<AppEvent>) {
+        let pads = Gamepad::get_all();
+        if self.gamepads.len() < pads.len() {
+            self.gamepads.resize(pads.len(), None);
+        }
+        for (id, slot) in self.gamepads.iter_mut().enumerate() {
+            let pad = match pads.get(id) {
+                Some(&Some(ref pad)) if pad.connected() => pad,
+                _ => {
+                    if slot.take().is_some() {
+                        events.push(AppEvent::GamepadDisconnected(id));
+                    }
+                    continue;
+                }
+            };
+            if slot.is_none() {
+                *slot = Some(GamepadState::new(id, pad.id()));
+                events.push(AppEvent::GamepadConnected(id));
+            }
+            let state = slot.as_mut().unwrap();
+            // without a standard mapping, the button/axis order is device specific
+            let standard = pad.mapping() == GamepadMappingType::Standard;
+            for (i, button) in pad.buttons().iter().enumerate() {
+                if let Some(b) = GamepadButton::from_index(i) {
+                    state.set_button(b, button.pressed(), events);
+                    if standard && b == GamepadButton::LeftTrigger {
+                        state.set_axis(GamepadAxis::LeftTrigger, button.value() as f32, events);
+                    } else if standard && b == GamepadButton::RightTrigger {
+                        state.set_axis(GamepadAxis::RightTrigger, button.value() as f32, events);
+                    }
+                }
+            }
+            for (i, value) in pad.axes().iter().enumerate() {
+                if let Some(axis) = GamepadAxis::from_index(i) {
+                    state.set_axis(axis, *value as f32, events);
+                }
+            }
+        }
+    }
+
+    pub fn gamepads<'a>(&'a self) -> Box<dyn Iterator<Item = &'a GamepadState> + 'a> {
+        Box::new(self.gamepads.iter().flatten())
+    }
+}